@@ -0,0 +1,81 @@
+// Step time as scenes grow, run with `cargo bench -p box2d`.
+// The time per body should stay flat when the solver scales linearly.
+
+use box2d::{Body, Real, Vec2, World, real};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+
+const WARMUP_STEPS: usize = 10;
+const STEPS: usize = 60;
+
+fn add_body(
+    world: &mut World,
+    w: Real,
+    h: Real,
+    mass: Real,
+    x: Real,
+    y: Real,
+) -> Rc<RefCell<Body>> {
+    let body = Rc::new(RefCell::new(Body::new(w, h, mass, x, y)));
+    world.add_body(body.clone());
+    body
+}
+
+// The pyramid demo scene with `rows` rows, rows * (rows + 1) / 2 boxes
+fn pyramid(rows: usize) -> World {
+    let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), 10);
+    let w = real(1.0);
+    let gap = w / real(3.0);
+    let floor_width = w * real(rows as f64 + 4.0);
+    add_body(&mut world, floor_width, w, Real::MAX, real(0.0), real(-0.5));
+    let start = real(-0.5 * rows as f64) * w;
+    for j in 0..rows {
+        for i in 0..rows - j {
+            let (i, j) = (real(i as f64), real(j as f64));
+            let x = start + i * w + j * w * real(0.5);
+            let y = w * real(0.5) + j * (w + gap) + gap;
+            add_body(&mut world, w, w, real(1.0), x, y);
+        }
+    }
+    world
+}
+
+// Horizontal chain of `links` boxes hanging from the ground by revolute joints
+fn chain(links: usize) -> World {
+    let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), 10);
+    let mut previous = world.ground();
+    for i in 0..links {
+        let x = real(i as f64 + 0.5);
+        let link = add_body(&mut world, real(1.0), real(0.25), real(1.0), x, real(0.0));
+        world.add_revolute_joint(previous, link.clone(), Vec2::new(x - real(0.5), real(0.0)));
+        previous = link;
+    }
+    world
+}
+
+fn bench(name: &str, sizes: &[usize], build: fn(usize) -> World) {
+    for &size in sizes {
+        let mut world = build(size);
+        let dt = real(1.0 / 60.0);
+        for _ in 0..WARMUP_STEPS {
+            world.step(dt);
+        }
+        let start = Instant::now();
+        for _ in 0..STEPS {
+            world.step(dt);
+        }
+        let per_step = start.elapsed().as_secs_f64() / STEPS as f64;
+        let bodies = world.bodies_size();
+        println!(
+            "{name:>8} {bodies:>6} bodies: {:>9.3} ms/step {:>7.3} us/body",
+            per_step * 1e3,
+            per_step * 1e6 / bodies as f64
+        );
+    }
+}
+
+fn main() {
+    bench("pyramid", &[12, 24, 48, 96], pyramid);
+    bench("chain", &[50, 200, 800, 3200], chain);
+}
@@ -1,18 +1,27 @@
 use crate::body::Body;
 use crate::collide::collide;
+use crate::constraint::{MAX_LINEAR_CORRECTION, Softness};
 use crate::contact::{Contact, MAX_CONTACT_POINT};
-use crate::math_utils::{Vec2, clamp, cross_f_v, cross_v_f, dot};
-use crate::world::{ACCUMULATE_IMPULSES, POSITION_CORRECTION, WARM_STARTING};
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::math_utils::{Mat22, Real, Vec2, clamp, cross_v_f, cross_v_v, dot, real, sqrt};
+use crate::solver_bodies::SolverBodies;
+use crate::world::{ACCUMULATE_IMPULSES, POSITION_CORRECTION, PositionCorrection, WARM_STARTING};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
 
 pub struct Arbiter {
     contacts: [Contact; MAX_CONTACT_POINT],
     num_contacts: usize,
-    body1: Rc<RefCell<Body>>,
-    body2: Rc<RefCell<Body>>,
+    // Body ids, body1 has the smaller one
+    body1: usize,
+    body2: usize,
     // Combined friction
-    friction: f32,
+    friction: Real,
+    position_correction: PositionCorrection,
+    // Both normal impulses of a two-point manifold are solved as a 2x2 LCP
+    block_solve: bool,
+    k: Mat22,
+    normal_mass: Mat22, // inverse of k
 }
 
 impl Arbiter {
@@ -23,20 +32,22 @@ impl Arbiter {
             (b2, b1)
         };
         let mut contacts: [Contact; MAX_CONTACT_POINT] = Default::default();
-        let body1 = b1.clone();
-        let body1 = &body1.borrow();
-        let body2 = b2.clone();
-        let body2 = &body2.borrow();
+        let body1 = &b1.borrow();
+        let body2 = &b2.borrow();
 
         let num_contacts = collide(&mut contacts, body1, body2);
-        let friction = f32::sqrt(body1.friction * body2.friction);
+        let friction = sqrt(body1.friction * body2.friction);
         (
             Self {
                 contacts,
                 num_contacts,
-                body1: b1,
-                body2: b2,
+                body1: body1.serial_number,
+                body2: body2.serial_number,
                 friction,
+                position_correction: PositionCorrection::default(),
+                block_solve: false,
+                k: Mat22::default(),
+                normal_mass: Mat22::default(),
             },
             num_contacts,
         )
@@ -81,9 +92,9 @@ impl Arbiter {
                     merged_contacts[i].pt = c_old.pt;
                     merged_contacts[i].pnb = c_old.pnb;
                 } else {
-                    merged_contacts[i].pn = 0.0;
-                    merged_contacts[i].pt = 0.0;
-                    merged_contacts[i].pnb = 0.0;
+                    merged_contacts[i].pn = real(0.0);
+                    merged_contacts[i].pt = real(0.0);
+                    merged_contacts[i].pnb = real(0.0);
                 }
             } else {
                 merged_contacts[i] = new_contacts[i].clone();
@@ -93,119 +104,383 @@ impl Arbiter {
         self.num_contacts = num_new_contacts;
     }
 
-    pub fn pre_step(&mut self, inv_dt: f32) {
-        const K_ALLOWED_PENETRATION: f32 = 0.01;
+    pub fn pre_step(
+        &mut self,
+        bodies: &mut SolverBodies,
+        inv_dt: Real,
+        position_correction: PositionCorrection,
+        block_solver: bool,
+    ) {
+        const K_ALLOWED_PENETRATION: Real = real(0.01);
         let k_bias_factor = if POSITION_CORRECTION { 0.2 } else { 0.0 };
+        self.position_correction = position_correction;
+        self.prepare(bodies);
 
         for i in 0..self.num_contacts {
             let c = &mut self.contacts[i];
-            let (tangent, r1, r2) = {
-                let body1 = self.body1.borrow();
-                let body2 = self.body2.borrow();
-
-                let r1 = c.position - body1.position;
-                let r2 = c.position - body2.position;
-
-                // Precompute normal mass, tangent mass, and bias.
-                let rn1 = dot(r1, c.normal);
-                let rn2 = dot(r2, c.normal);
-                let k_normal = body1.inv_mass
-                    + body2.inv_mass
-                    + body1.inv_i * (dot(r1, r1) - rn1 * rn1)
-                    + body2.inv_i * (dot(r2, r2) - rn2 * rn2);
-                c.mass_normal = 1.0 / k_normal;
-
-                let tangent = cross_v_f(c.normal, 1.0);
-                let rt1 = dot(r1, tangent);
-                let rt2 = dot(r2, tangent);
-                let k_tangent = body1.inv_mass
-                    + body2.inv_mass
-                    + body1.inv_i * (dot(r1, r1) - rt1 * rt1)
-                    + body2.inv_i * (dot(r2, r2) - rt2 * rt2);
-                c.mass_tangent = 1.0 / k_tangent;
-
-                c.bias =
-                    -k_bias_factor * inv_dt * f32::min(0.0, c.separation + K_ALLOWED_PENETRATION);
-                (tangent, r1, r2)
-            };
+            c.bias = -k_bias_factor
+                * inv_dt
+                * Real::min(real(0.0), c.separation + K_ALLOWED_PENETRATION);
 
-            if ACCUMULATE_IMPULSES {
-                // Apply normal + friction impulse
-                let p = (c.pn * c.normal) + (c.pt * tangent);
-                self.body1.borrow_mut().sub_velocity(r1, p);
-                self.body2.borrow_mut().add_velocity(r2, p);
+            if self.position_correction == PositionCorrection::SplitImpulse && ACCUMULATE_IMPULSES {
+                // Apply position impulse to the pseudo-velocities
+                let p = c.pnb * c.normal;
+                bodies.sub_bias_velocity(self.body1, c.r1, p);
+                bodies.add_bias_velocity(self.body2, c.r2, p);
+            } else {
+                c.pnb = real(0.0);
             }
         }
+
+        if ACCUMULATE_IMPULSES {
+            self.warm_start(bodies);
+        }
+
+        self.block_solve = block_solver && ACCUMULATE_IMPULSES && self.prepare_block_solve(bodies);
+    }
+
+    // Precompute anchors, normal mass and tangent mass.
+    fn prepare(&mut self, bodies: &SolverBodies) {
+        let (body1, body2) = (self.body1, self.body2);
+
+        for c in &mut self.contacts[..self.num_contacts] {
+            let r1 = c.position - bodies.position(body1);
+            let r2 = c.position - bodies.position(body2);
+            c.r1 = r1;
+            c.r2 = r2;
+            c.local_anchor1 = Mat22::from(bodies.rot(body1)).transpose() * r1;
+            c.local_anchor2 = Mat22::from(bodies.rot(body2)).transpose() * r2;
+
+            let rn1 = dot(r1, c.normal);
+            let rn2 = dot(r2, c.normal);
+            let k_normal = bodies.inv_mass_along(body1, c.normal)
+                + bodies.inv_mass_along(body2, c.normal)
+                + bodies.angular_inv_i(body1) * (dot(r1, r1) - rn1 * rn1)
+                + bodies.angular_inv_i(body2) * (dot(r2, r2) - rn2 * rn2);
+            c.mass_normal = if k_normal > 0.0 {
+                1.0 / k_normal
+            } else {
+                real(0.0)
+            };
+
+            let tangent = cross_v_f(c.normal, real(1.0));
+            let rt1 = dot(r1, tangent);
+            let rt2 = dot(r2, tangent);
+            let k_tangent = bodies.inv_mass_along(body1, tangent)
+                + bodies.inv_mass_along(body2, tangent)
+                + bodies.angular_inv_i(body1) * (dot(r1, r1) - rt1 * rt1)
+                + bodies.angular_inv_i(body2) * (dot(r2, r2) - rt2 * rt2);
+            c.mass_tangent = if k_tangent > 0.0 { 1.0 / k_tangent } else { real(0.0) };
+        }
+    }
+
+    // Apply normal + friction impulse
+    pub fn warm_start(&mut self, bodies: &mut SolverBodies) {
+        for c in &self.contacts[..self.num_contacts] {
+            let tangent = cross_v_f(c.normal, real(1.0));
+            let p = (c.pn * c.normal) + (c.pt * tangent);
+            bodies.sub_velocity(self.body1, c.r1, p);
+            bodies.add_velocity(self.body2, c.r2, p);
+        }
+    }
+
+    // Build the 2x2 normal mass matrix, false if it is missing or ill-conditioned
+    fn prepare_block_solve(&mut self, bodies: &SolverBodies) -> bool {
+        if self.num_contacts != 2 {
+            return false;
+        }
+        let (body1, body2) = (self.body1, self.body2);
+        let (c1, c2) = (&self.contacts[0], &self.contacts[1]);
+        let normal = c1.normal;
+
+        let rn11 = cross_v_v(c1.r1, normal);
+        let rn12 = cross_v_v(c1.r2, normal);
+        let rn21 = cross_v_v(c2.r1, normal);
+        let rn22 = cross_v_v(c2.r2, normal);
+        let inv_mass = bodies.inv_mass_along(body1, normal) + bodies.inv_mass_along(body2, normal);
+        let inv_i1 = bodies.angular_inv_i(body1);
+        let inv_i2 = bodies.angular_inv_i(body2);
+
+        let k11 = inv_mass + inv_i1 * rn11 * rn11 + inv_i2 * rn12 * rn12;
+        let k22 = inv_mass + inv_i1 * rn21 * rn21 + inv_i2 * rn22 * rn22;
+        let k12 = inv_mass + inv_i1 * rn11 * rn21 + inv_i2 * rn12 * rn22;
+
+        // Ensure a reasonable condition number
+        const K_MAX_CONDITION_NUMBER: Real = real(1000.0);
+        if k11 * k11 >= K_MAX_CONDITION_NUMBER * (k11 * k22 - k12 * k12) {
+            return false;
+        }
+        self.k = Mat22::new(Vec2::new(k11, k12), Vec2::new(k12, k22));
+        self.normal_mass = self.k.invert();
+        true
     }
 
-    pub fn apply_impulse(&mut self) {
+    pub fn apply_impulse(&mut self, bodies: &mut SolverBodies) {
+        if self.block_solve {
+            // Box2D order: friction first, bounded by the normal impulses of
+            // the previous iteration, then both normal impulses together
+            for i in 0..self.num_contacts {
+                self.apply_friction_impulse(bodies, i, real(0.0));
+            }
+            self.apply_block_normal_impulse(bodies);
+            return;
+        }
+        let (body1, body2) = (self.body1, self.body2);
+
         for i in 0..self.num_contacts {
             let c = &mut self.contacts[i];
 
             let (dpn, pn) = {
-                let body1 = self.body1.borrow();
-                let body2 = self.body2.borrow();
-                c.r1 = c.position - body1.position;
-                c.r2 = c.position - body2.position;
+                c.r1 = c.position - bodies.position(body1);
+                c.r2 = c.position - bodies.position(body2);
 
                 // Relative velocity at contact
-                let dv = (body2.velocity + cross_f_v(body2.angular_velocity, c.r2))
-                    - (body1.velocity + cross_f_v(body1.angular_velocity, c.r1));
+                let dv = bodies.velocity_at(body2, c.r2) - bodies.velocity_at(body1, c.r1);
 
                 // Compute normal impulse
                 let vn = dot(dv, c.normal);
 
-                let dpn = c.mass_normal * (-vn + c.bias);
+                // Other modes resolve penetration outside the velocity solve
+                let bias = match self.position_correction {
+                    PositionCorrection::Baumgarte => c.bias,
+                    _ => real(0.0),
+                };
+                let dpn = c.mass_normal * (-vn + bias);
                 let dpn = if ACCUMULATE_IMPULSES {
                     // Clamp the accumulated impulse
                     let pn0 = c.pn;
-                    c.pn = f32::max(pn0 + dpn, 0.0);
+                    c.pn = Real::max(pn0 + dpn, real(0.0));
                     c.pn - pn0
                 } else {
-                    f32::max(dpn, 0.0)
+                    Real::max(dpn, real(0.0))
                 };
 
                 // Apply contact impulse
                 (dpn, dpn * c.normal)
             };
-            self.body1.borrow_mut().sub_velocity(c.r1, pn);
-            self.body2.borrow_mut().add_velocity(c.r2, pn);
+            bodies.sub_velocity(body1, c.r1, pn);
+            bodies.add_velocity(body2, c.r2, pn);
 
-            let pt = {
-                let body1 = self.body1.borrow();
-                let body2 = self.body2.borrow();
-                // Relative velocity at contact
-                let dv = (body2.velocity + cross_f_v(body2.angular_velocity, c.r2))
-                    - (body1.velocity + cross_f_v(body1.angular_velocity, c.r1));
+            self.apply_friction_impulse(bodies, i, dpn);
+        }
+    }
 
-                let tangent = cross_v_f(c.normal, 1.0);
-                let vt = dot(dv, tangent);
-                let dpt = c.mass_tangent * (-vt);
+    // `dpn` bounds the friction impulse when impulses are not accumulated
+    fn apply_friction_impulse(&mut self, bodies: &mut SolverBodies, i: usize, dpn: Real) {
+        let (body1, body2) = (self.body1, self.body2);
+        let c = &mut self.contacts[i];
+
+        let pt = {
+            // Relative velocity at contact
+            let dv = bodies.velocity_at(body2, c.r2) - bodies.velocity_at(body1, c.r1);
+
+            let tangent = cross_v_f(c.normal, real(1.0));
+            let vt = dot(dv, tangent);
+            let dpt = c.mass_tangent * (-vt);
+
+            let dpt = if ACCUMULATE_IMPULSES {
+                // Compute friction impulse
+                let max_pt = self.friction * c.pn;
+
+                // Clamp friction
+                let old_tangent_impulse = c.pt;
+                c.pt = clamp(old_tangent_impulse + dpt, -max_pt, max_pt);
+                c.pt - old_tangent_impulse
+            } else {
+                let max_pt = self.friction * dpn;
+                clamp(dpt, -max_pt, max_pt)
+            };
+            // Apply contact impulse
+            dpt * tangent
+        };
+        bodies.sub_velocity(body1, c.r1, pt);
+        bodies.add_velocity(body2, c.r2, pt);
+    }
+
+    // Soft step: contact mass and anchors stay fixed for all substeps
+    pub fn prepare_soft_step(&mut self, bodies: &SolverBodies) {
+        self.block_solve = false;
+        self.prepare(bodies);
+    }
 
-                let dpt = if ACCUMULATE_IMPULSES {
-                    // Compute friction impulse
-                    let max_pt = self.friction * c.pn;
+    // One soft step iteration. The separation is updated from the moved
+    // bodies; `softness` is None for the relax pass that removes the bias.
+    pub fn solve_soft_step(
+        &mut self,
+        bodies: &mut SolverBodies,
+        inv_h: Real,
+        softness: Option<Softness>,
+        max_bias_velocity: Real,
+    ) {
+        let (body1, body2) = (self.body1, self.body2);
 
-                    // Clamp friction
-                    let old_tangent_impulse = c.pt;
-                    c.pt = clamp(old_tangent_impulse + dpt, -max_pt, max_pt);
-                    c.pt - old_tangent_impulse
+        for c in &mut self.contacts[..self.num_contacts] {
+            let p1 = bodies.transform(body1) * c.local_anchor1;
+            let p2 = bodies.transform(body2) * c.local_anchor2;
+            let separation = c.separation + dot(p2 - p1, c.normal);
+
+            let (bias, mass_scale, impulse_scale) = match softness {
+                // Speculative: allow the gap to close within the substep
+                _ if separation > 0.0 => (separation * inv_h, real(1.0), real(0.0)),
+                Some(soft) if POSITION_CORRECTION => (
+                    Real::max(soft.bias_rate * separation, -max_bias_velocity),
+                    soft.mass_scale,
+                    soft.impulse_scale,
+                ),
+                _ => (real(0.0), real(1.0), real(0.0)),
+            };
+
+            // Normal impulse
+            let dv = bodies.velocity_at(body2, c.r2) - bodies.velocity_at(body1, c.r1);
+            let vn = dot(dv, c.normal);
+            let dpn = -c.mass_normal * mass_scale * (vn + bias) - impulse_scale * c.pn;
+
+            // Clamp the accumulated impulse
+            let pn0 = c.pn;
+            c.pn = Real::max(pn0 + dpn, real(0.0));
+            let pn = (c.pn - pn0) * c.normal;
+            bodies.sub_velocity(body1, c.r1, pn);
+            bodies.add_velocity(body2, c.r2, pn);
+
+            // Friction impulse
+            let dv = bodies.velocity_at(body2, c.r2) - bodies.velocity_at(body1, c.r1);
+            let tangent = cross_v_f(c.normal, real(1.0));
+            let dpt = -c.mass_tangent * dot(dv, tangent);
+
+            // Clamp friction
+            let max_pt = self.friction * c.pn;
+            let pt0 = c.pt;
+            c.pt = clamp(pt0 + dpt, -max_pt, max_pt);
+            let pt = (c.pt - pt0) * tangent;
+            bodies.sub_velocity(body1, c.r1, pt);
+            bodies.add_velocity(body2, c.r2, pt);
+        }
+    }
+
+    // Solve both normal impulses together:
+    // vn = K * x + b with vn >= 0, x >= 0 and vn_i * x_i = 0.
+    // Try the four complementarity cases, see Box2D b2ContactSolver.
+    fn apply_block_normal_impulse(&mut self, bodies: &mut SolverBodies) {
+        let (body1, body2) = (self.body1, self.body2);
+        let (c1, c2) = (&self.contacts[0], &self.contacts[1]);
+        let normal = c1.normal;
+        let a = Vec2::new(c1.pn, c2.pn);
+
+        let (vn1, vn2) = {
+            let dv1 = bodies.velocity_at(body2, c1.r2) - bodies.velocity_at(body1, c1.r1);
+            let dv2 = bodies.velocity_at(body2, c2.r2) - bodies.velocity_at(body1, c2.r1);
+            (dot(dv1, normal), dot(dv2, normal))
+        };
+
+        let (bias1, bias2) = match self.position_correction {
+            PositionCorrection::Baumgarte => (c1.bias, c2.bias),
+            _ => (real(0.0), real(0.0)),
+        };
+        let b = Vec2::new(vn1 - bias1, vn2 - bias2) - self.k * a;
+        let (k11, k12, k22) = (self.k.col1.x, self.k.col2.x, self.k.col2.y);
+
+        let x = 'solved: {
+            // Case 1: both contacts active
+            let x = -(self.normal_mass * b);
+            if x.x >= 0.0 && x.y >= 0.0 {
+                break 'solved x;
+            }
+            // Case 2: only the first contact active
+            let x1 = -b.x / k11;
+            if x1 >= 0.0 && k12 * x1 + b.y >= 0.0 {
+                break 'solved Vec2::new(x1, real(0.0));
+            }
+            // Case 3: only the second contact active
+            let x2 = -b.y / k22;
+            if x2 >= 0.0 && k12 * x2 + b.x >= 0.0 {
+                break 'solved Vec2::new(real(0.0), x2);
+            }
+            // Case 4: both contacts separating
+            if b.x >= 0.0 && b.y >= 0.0 {
+                break 'solved Vec2::default();
+            }
+            // No solution, keep the impulses
+            a
+        };
+
+        let d = x - a;
+        let (r11, r12, r21, r22) = (c1.r1, c1.r2, c2.r1, c2.r2);
+        self.contacts[0].pn = x.x;
+        self.contacts[1].pn = x.y;
+
+        bodies.sub_velocity(body1, r11, d.x * normal);
+        bodies.add_velocity(body2, r12, d.x * normal);
+        bodies.sub_velocity(body1, r21, d.y * normal);
+        bodies.add_velocity(body2, r22, d.y * normal);
+    }
+
+    // Split impulse: push penetrating bodies apart through pseudo-velocities
+    // that move the bodies in this step only and add no energy
+    pub fn apply_position_impulse(&mut self, bodies: &mut SolverBodies) {
+        let (body1, body2) = (self.body1, self.body2);
+        for i in 0..self.num_contacts {
+            let c = &mut self.contacts[i];
+
+            let pnb = {
+                // Relative pseudo-velocity at contact
+                let dv =
+                    bodies.bias_velocity_at(body2, c.r2) - bodies.bias_velocity_at(body1, c.r1);
+                let vn = dot(dv, c.normal);
+
+                let dpnb = c.mass_normal * (-vn + c.bias);
+                let dpnb = if ACCUMULATE_IMPULSES {
+                    // Clamp the accumulated impulse
+                    let pnb0 = c.pnb;
+                    c.pnb = Real::max(pnb0 + dpnb, real(0.0));
+                    c.pnb - pnb0
                 } else {
-                    let max_pt = self.friction * dpn;
-                    clamp(dpt, -max_pt, max_pt)
+                    Real::max(dpnb, real(0.0))
                 };
-                // Apply contact impulse
-                dpt * tangent
+                dpnb * c.normal
             };
-            self.body1.borrow_mut().sub_velocity(c.r1, pt);
-            self.body2.borrow_mut().add_velocity(c.r2, pt);
+            bodies.sub_bias_velocity(body1, c.r1, pnb);
+            bodies.add_bias_velocity(body2, c.r2, pnb);
         }
     }
 
+    // NGS: push the bodies apart along the contact normals using their current
+    // transforms, returns the smallest separation
+    pub fn solve_position(&mut self, bodies: &mut SolverBodies, linear_slop: Real) -> Real {
+        const K_BIAS_FACTOR: Real = real(0.2);
+        let max_correction = MAX_LINEAR_CORRECTION * linear_slop;
+        let (body1, body2) = (self.body1, self.body2);
+        let mut min_separation = real(0.0);
+
+        for c in &self.contacts[..self.num_contacts] {
+            let r1 = Mat22::from(bodies.rot(body1)) * c.local_anchor1;
+            let r2 = Mat22::from(bodies.rot(body2)) * c.local_anchor2;
+
+            // The anchors coincide at pre_step, their drift changes the separation
+            let d = (bodies.position(body2) + r2) - (bodies.position(body1) + r1);
+            let separation = c.separation + dot(d, c.normal);
+            min_separation = Real::min(min_separation, separation);
+
+            let correction = clamp(
+                K_BIAS_FACTOR * (separation + linear_slop),
+                -max_correction,
+                real(0.0),
+            );
+            let rn1 = cross_v_v(r1, c.normal);
+            let rn2 = cross_v_v(r2, c.normal);
+            let k = bodies.inv_mass_along(body1, c.normal)
+                + bodies.inv_mass_along(body2, c.normal)
+                + bodies.angular_inv_i(body1) * rn1 * rn1
+                + bodies.angular_inv_i(body2) * rn2 * rn2;
+            let impulse = if k > 0.0 { -correction / k } else { real(0.0) };
+
+            let p = impulse * c.normal;
+            bodies.sub_position(body1, r1, p);
+            bodies.add_position(body2, r2, p);
+        }
+        min_separation
+    }
+
     pub fn get_key(&self) -> (usize, usize) {
-        (
-            self.body1.borrow().serial_number,
-            self.body2.borrow().serial_number,
-        )
+        (self.body1, self.body2)
     }
 }
@@ -1,64 +1,88 @@
-use crate::math_utils::{Vec2, cross_v_v};
+use crate::constraint::JointEdge;
+use crate::math_utils::{
+    Mat22, Real, Rot, Transform, Vec2, cross_f_v, cross_v_v, real, unwind_angle,
+};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
 
-pub const UNMOVABLE_MASS: f32 = f32::MAX;
+pub const UNMOVABLE_MASS: Real = Real::MAX;
 
 pub struct Body {
     pub position: Vec2, // in the middle of body
-    pub rotation: f32,
+    // Angle in (-pi, pi] and its cosine and sine, always kept in sync.
+    // No longer public fields: read them with rotation() and rot(), write
+    // with set_rotation(). Track full turns yourself if you need them.
+    pub(crate) rotation: Real,
+    pub(crate) rot: Rot,
 
     pub velocity: Vec2,
-    pub angular_velocity: f32,
+    pub angular_velocity: Real,
 
     pub force: Vec2,
-    pub torque: f32,
+    pub torque: Real,
 
     pub width: Vec2,
 
-    pub friction: f32,
-    mass: f32,
-    pub inv_mass: f32,
-    i: f32,
-    pub inv_i: f32,
+    pub friction: Real,
+
+    // Locked degrees of freedom are solved as if they had infinite inertia
+    pub fixed_rotation: bool,
+    pub lock_translation_x: bool,
+    pub lock_translation_y: bool,
+
+    mass: Real,
+    pub inv_mass: Real,
+    i: Real,
+    pub inv_i: Real,
 
     pub serial_number: usize, // for arbiter instead of compare addresses
+
+    pub(crate) joint_edges: Vec<JointEdge>,
 }
 
 impl Default for Body {
     fn default() -> Self {
         Self {
             position: Default::default(),
-            rotation: 0.0,
+            rotation: real(0.0),
+            rot: Rot::IDENTITY,
             velocity: Default::default(),
-            angular_velocity: 0.0,
+            angular_velocity: real(0.0),
             force: Default::default(),
-            torque: 0.0,
-            width: Vec2::new(1.0, 1.0),
-            friction: 0.0,
+            torque: real(0.0),
+            width: Vec2::new(real(1.0), real(1.0)),
+            friction: real(0.0),
+            fixed_rotation: false,
+            lock_translation_x: false,
+            lock_translation_y: false,
             mass: UNMOVABLE_MASS,
-            inv_mass: 0.0,
+            inv_mass: real(0.0),
             i: UNMOVABLE_MASS,
-            inv_i: 0.0,
+            inv_i: real(0.0),
             serial_number: 0,
+            joint_edges: Vec::new(),
         }
     }
 }
 
 impl Body {
-    pub fn new(w: f32, h: f32, mass: f32, pos_x: f32, pos_y: f32) -> Self {
+    pub fn new(w: Real, h: Real, mass: Real, pos_x: Real, pos_y: Real) -> Self {
         let mut body = Self::default();
         body.set(Vec2::new(w, h), mass);
         body.position.set(pos_x, pos_y);
         body
     }
 
-    pub fn set(&mut self, w: Vec2, m: f32) {
-        self.position.set(0.0, 0.0);
-        self.rotation = 0.0;
-        self.velocity.set(0.0, 0.0);
-        self.angular_velocity = 0.0;
-        self.force.set(0.0, 0.0);
-        self.torque = 0.0;
-        self.friction = 0.2;
+    pub fn set(&mut self, w: Vec2, m: Real) {
+        self.position.set(real(0.0), real(0.0));
+        self.rotation = real(0.0);
+        self.rot = Rot::IDENTITY;
+        self.velocity.set(real(0.0), real(0.0));
+        self.angular_velocity = real(0.0);
+        self.force.set(real(0.0), real(0.0));
+        self.torque = real(0.0);
+        self.friction = real(0.2);
 
         self.width = w;
         self.mass = m;
@@ -68,23 +92,125 @@ impl Body {
             self.i = self.mass * (self.width.x * self.width.x + self.width.y * self.width.y) / 12.0;
             self.inv_i = 1.0 / self.i;
         } else {
-            self.inv_mass = 0.0;
+            self.inv_mass = real(0.0);
             self.i = UNMOVABLE_MASS;
-            self.inv_i = 0.0;
+            self.inv_i = real(0.0);
+        }
+    }
+
+    pub fn mass(&self) -> Real {
+        self.mass
+    }
+
+    pub fn rotation(&self) -> Real {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, angle: Real) {
+        self.rotation = angle;
+        self.sync_rotation();
+    }
+
+    pub fn rot(&self) -> Rot {
+        self.rot
+    }
+
+    // Body coordinates to world
+    pub fn transform(&self) -> Transform {
+        Transform::new(self.position, self.rot)
+    }
+
+    // Wraps the angle and refreshes its cosine and sine, the only trig a body
+    // needs per step
+    pub(crate) fn sync_rotation(&mut self) {
+        self.rotation = unwind_angle(self.rotation);
+        self.rot = Rot::from_angle(self.rotation);
+    }
+
+    // Whether a world point lies inside the box
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        let local = Mat22::from(self.rot).transpose() * (point - self.position);
+        let h = real(0.5) * self.width;
+        local.x.abs() <= h.x && local.y.abs() <= h.y
+    }
+
+    // False if a joint between this body and `other` disables their collision
+    pub(crate) fn collides_with(&self, other: &Rc<RefCell<Body>>) -> bool {
+        self.joint_edges.iter().all(|edge| {
+            !core::ptr::eq(edge.other.as_ptr(), Rc::as_ptr(other))
+                || edge
+                    .joint
+                    .upgrade()
+                    .is_none_or(|joint| joint.borrow().collide_connected())
+        })
+    }
+
+    // Inverse mass along world X and Y with translation locks applied
+    pub fn linear_inv_mass(&self) -> Vec2 {
+        Vec2::new(
+            if self.lock_translation_x { real(0.0) } else { self.inv_mass },
+            if self.lock_translation_y { real(0.0) } else { self.inv_mass },
+        )
+    }
+
+    // Inverse inertia with the rotation lock applied
+    pub fn angular_inv_i(&self) -> Real {
+        if self.fixed_rotation {
+            real(0.0)
+        } else {
+            self.inv_i
         }
     }
 
+    // Inverse mass seen by an impulse along the unit direction `n`
+    pub fn inv_mass_along(&self, n: Vec2) -> Real {
+        let inv_mass = self.linear_inv_mass();
+        inv_mass.x * n.x * n.x + inv_mass.y * n.y * n.y
+    }
+
     pub fn sub_velocity(&mut self, r: Vec2, p: Vec2) {
-        self.velocity -= self.inv_mass * p;
-        self.angular_velocity -= self.inv_i * cross_v_v(r, p);
+        let inv_mass = self.linear_inv_mass();
+        self.velocity -= Vec2::new(inv_mass.x * p.x, inv_mass.y * p.y);
+        self.angular_velocity -= self.angular_inv_i() * cross_v_v(r, p);
     }
 
     pub fn add_velocity(&mut self, r: Vec2, p: Vec2) {
-        self.velocity += self.inv_mass * p;
-        self.angular_velocity += self.inv_i * cross_v_v(r, p);
+        let inv_mass = self.linear_inv_mass();
+        self.velocity += Vec2::new(inv_mass.x * p.x, inv_mass.y * p.y);
+        self.angular_velocity += self.angular_inv_i() * cross_v_v(r, p);
     }
 
     pub fn add_force(&mut self, f: Vec2) {
         self.force += f;
     }
+
+    // Force applied at a world point also produces a torque about the center
+    pub fn apply_force_at_point(&mut self, f: Vec2, point: Vec2) {
+        self.force += f;
+        self.torque += cross_v_v(point - self.position, f);
+    }
+
+    pub fn apply_torque(&mut self, torque: Real) {
+        self.torque += torque;
+    }
+
+    // Impulse applied at a world point, changes velocity immediately
+    pub fn apply_linear_impulse(&mut self, impulse: Vec2, point: Vec2) {
+        self.add_velocity(point - self.position, impulse);
+    }
+
+    pub fn apply_angular_impulse(&mut self, impulse: Real) {
+        self.angular_velocity += self.angular_inv_i() * impulse;
+    }
+
+    // Velocity of the body material at a world point
+    pub fn velocity_at_point(&self, point: Vec2) -> Vec2 {
+        self.velocity + cross_f_v(self.angular_velocity, point - self.position)
+    }
+
+    // Velocity of the body material at a point given in body coordinates
+    pub fn velocity_at_local_point(&self, local_point: Vec2) -> Vec2 {
+        let r = Mat22::from(self.rot) * local_point;
+        self.velocity + cross_f_v(self.angular_velocity, r)
+    }
 }
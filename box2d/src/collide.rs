@@ -1,6 +1,6 @@
 use crate::body::Body;
 use crate::contact::{Contact, EdgeNumbers, Feature, MAX_CONTACT_POINT};
-use crate::math_utils::{Mat22, Vec2, dot};
+use crate::math_utils::{Mat22, Real, Vec2, dot, real};
 
 #[derive(Debug, PartialEq)]
 enum Axis {
@@ -31,7 +31,7 @@ fn clip_segment_to_line(
     v_out: &mut [ClipVertex; MAX_CONTACT_POINT],
     v_in: [ClipVertex; MAX_CONTACT_POINT],
     normal: Vec2,
-    offset: f32,
+    offset: Real,
     clip_edge: EdgeNumbers,
 ) -> usize {
     // Start with no output points
@@ -80,7 +80,7 @@ fn compute_incident_edge(
     let n_abs = n.abs();
 
     if n_abs.x > n_abs.y {
-        if f32::signum(n.x) > 0.0 {
+        if Real::signum(n.x) > 0.0 {
             c[0].v.set(h.x, -h.y);
             c[0].fp.in_edge2 = EdgeNumbers::Edge3;
             c[0].fp.out_edge2 = EdgeNumbers::Edge4;
@@ -98,7 +98,7 @@ fn compute_incident_edge(
             c[1].fp.out_edge2 = EdgeNumbers::Edge3;
         }
     } else {
-        if f32::signum(n.y) > 0.0 {
+        if Real::signum(n.y) > 0.0 {
             c[0].v.set(h.x, h.y);
             c[0].fp.in_edge2 = EdgeNumbers::Edge4;
             c[0].fp.out_edge2 = EdgeNumbers::Edge1;
@@ -123,14 +123,14 @@ fn compute_incident_edge(
 
 pub fn collide(contacts: &mut [Contact; MAX_CONTACT_POINT], body_a: &Body, body_b: &Body) -> usize {
     // Setup
-    let h_a = 0.5 * body_a.width;
-    let h_b = 0.5 * body_b.width;
+    let h_a = real(0.5) * body_a.width;
+    let h_b = real(0.5) * body_b.width;
 
     let pos_a = body_a.position;
     let pos_b = body_b.position;
 
-    let rot_a = Mat22::from_angle(body_a.rotation);
-    let rot_b = Mat22::from_angle(body_b.rotation);
+    let rot_a = Mat22::from(body_a.rot);
+    let rot_b = Mat22::from(body_b.rot);
 
     let rot_at = rot_a.transpose();
     let rot_bt = rot_b.transpose();
@@ -160,8 +160,8 @@ pub fn collide(contacts: &mut [Contact; MAX_CONTACT_POINT], body_a: &Body, body_
     let mut separation = face_a.x;
     let mut normal = if da.x > 0.0 { rot_a.col1 } else { -rot_a.col1 };
 
-    const RELATIVE_TOL: f32 = 0.95;
-    const ABSOLUTE_TOL: f32 = 0.01;
+    const RELATIVE_TOL: Real = real(0.95);
+    const ABSOLUTE_TOL: Real = real(0.01);
 
     if face_a.y > RELATIVE_TOL * separation + ABSOLUTE_TOL * h_a.y {
         axis = Axis::FaceAY;
@@ -0,0 +1,268 @@
+use crate::body::Body;
+use crate::math_utils::{PI, Real, Vec2, real};
+use crate::solver_bodies::SolverBodies;
+use crate::world::POSITION_CORRECTION;
+use alloc::boxed::Box;
+use alloc::rc::{Rc, Weak};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// What World::step hands constraints while solving
+pub struct StepContext<'a> {
+    // Body state during the step. Bodies are not borrowed by World while
+    // constraints run, but it overwrites them with these arrays after the
+    // step: read and write velocities and positions here, not on Body.
+    pub bodies: &'a mut SolverBodies,
+    pub inv_dt: Real,
+    // solve_position runs after the velocity pass
+    // (PositionCorrection::NonlinearGaussSeidel). Constraints implementing
+    // it leave the error it corrects out of their velocity bias, otherwise
+    // that error is corrected twice.
+    pub solve_positions: bool,
+    // Set in SolverMode::SoftStep: rigid rows are solved as this spring
+    // instead of with their bias factor
+    pub softness: Option<Softness>,
+    // Substep of SolverMode::SoftStep, 0 otherwise. Work meant to happen
+    // once per step, like damping, is done in substep 0.
+    pub substep: i32,
+    // False in the relax iteration of the soft step, which only removes
+    // velocity error: no position bias and no softening
+    pub use_bias: bool,
+    // Error solve_position may leave in place
+    pub linear_slop: Real,
+}
+
+impl StepContext<'_> {
+    // Softness of a rigid row, `bias_factor` is its Baumgarte factor
+    // outside the soft step
+    pub fn rigid_softness(&self, bias_factor: Real) -> Softness {
+        self.softness.unwrap_or_else(|| self.baumgarte(bias_factor))
+    }
+
+    // Rigid row corrected with `bias_factor`, whatever the solver mode
+    pub fn baumgarte(&self, bias_factor: Real) -> Softness {
+        if POSITION_CORRECTION {
+            Softness {
+                bias_rate: bias_factor * self.inv_dt,
+                ..Softness::RIGID
+            }
+        } else {
+            Softness::RIGID
+        }
+    }
+}
+
+// Solver protocol shared by every joint type stored in World. Implement it
+// for game-specific constraints and register them with World::add_constraint.
+// Each step World calls pre_step once and apply_impulse `iterations` times
+// (SolverMode::SoftStep: both once per substep, plus a relax apply_impulse).
+// Look up the ids of the bodies in pre_step with `step.bodies.id` and work
+// on `step.bodies` through them.
+pub trait Constraint {
+    fn pre_step(&mut self, step: &mut StepContext);
+
+    fn apply_impulse(&mut self, step: &mut StepContext);
+
+    // Every body the constraint acts on
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>>;
+
+    // Bodies kept from colliding with each other unless collide_connected,
+    // all of `bodies` by default
+    fn connected_bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        self.bodies()
+    }
+
+    // Whether the connected bodies still collide with each other
+    fn collide_connected(&self) -> bool {
+        false
+    }
+
+    // Joints this constraint couples. World breaks it together with any of
+    // them, when they break or get removed.
+    fn coupled_joints(&self) -> Vec<Rc<RefCell<dyn Constraint>>> {
+        Vec::new()
+    }
+
+    // Position pass of PositionCorrection::NonlinearGaussSeidel: correct the
+    // error of the current transforms directly, return true once it is within
+    // `step.linear_slop`. Constraints without it rely on their velocity bias.
+    fn solve_position(&mut self, _step: &mut StepContext) -> bool {
+        true
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        Vec::new()
+    }
+
+    // Force and torque applied to body2 over the last step,
+    // i.e. the accumulated impulse divided by the timestep
+    fn reaction_force(&self, _inv_dt: Real) -> Vec2 {
+        Vec2::default()
+    }
+
+    fn reaction_torque(&self, _inv_dt: Real) -> Real {
+        real(0.0)
+    }
+
+    // World::step removes the joint once a reaction exceeds its threshold
+    fn break_force(&self) -> Option<Real> {
+        None
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        None
+    }
+
+    fn is_broken(&self, inv_dt: Real) -> bool {
+        self.break_force()
+            .is_some_and(|max_force| self.reaction_force(inv_dt).length() > max_force)
+            || self
+                .break_torque()
+                .is_some_and(|max_torque| self.reaction_torque(inv_dt).abs() > max_torque)
+    }
+}
+
+// Lets World::add_constraint take an already boxed `Box<dyn Constraint>`
+impl<T: Constraint + ?Sized> Constraint for Box<T> {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        (**self).pre_step(step)
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        (**self).apply_impulse(step)
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        (**self).bodies()
+    }
+
+    fn connected_bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        (**self).connected_bodies()
+    }
+
+    fn collide_connected(&self) -> bool {
+        (**self).collide_connected()
+    }
+
+    fn coupled_joints(&self) -> Vec<Rc<RefCell<dyn Constraint>>> {
+        (**self).coupled_joints()
+    }
+
+    fn solve_position(&mut self, step: &mut StepContext) -> bool {
+        (**self).solve_position(step)
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        (**self).get_joint_lines()
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        (**self).reaction_force(inv_dt)
+    }
+
+    fn reaction_torque(&self, inv_dt: Real) -> Real {
+        (**self).reaction_torque(inv_dt)
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        (**self).break_force()
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        (**self).break_torque()
+    }
+
+    fn is_broken(&self, inv_dt: Real) -> bool {
+        (**self).is_broken(inv_dt)
+    }
+}
+
+// Entry of a body's joint list: a joint and another body it connects to
+pub(crate) struct JointEdge {
+    pub(crate) other: Weak<RefCell<Body>>,
+    pub(crate) joint: Weak<RefCell<dyn Constraint>>,
+}
+
+// Largest position correction of one NGS iteration, in units of linear slop
+pub(crate) const MAX_LINEAR_CORRECTION: Real = real(40.0);
+
+// Soft constraint coefficients of a spring with frequency `hertz` and
+// `damping_ratio` solved with substep `h`, Box2D v3 style:
+// impulse = -mass * mass_scale * (vn + bias_rate * C) - impulse_scale * accumulated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Softness {
+    pub bias_rate: Real,
+    pub mass_scale: Real,
+    pub impulse_scale: Real,
+}
+
+impl Softness {
+    // Rigid without position correction
+    pub const RIGID: Softness = Softness {
+        bias_rate: real(0.0),
+        mass_scale: real(1.0),
+        impulse_scale: real(0.0),
+    };
+
+    pub fn new(hertz: Real, damping_ratio: Real, h: Real) -> Self {
+        if hertz <= 0.0 {
+            return Softness::RIGID;
+        }
+        let omega = 2.0 * PI * hertz;
+        let a1 = 2.0 * damping_ratio + h * omega;
+        let a2 = h * omega * a1;
+        let a3 = 1.0 / (1.0 + a2);
+        Self {
+            bias_rate: omega / a1,
+            mass_scale: a2 * a3,
+            impulse_scale: a3,
+        }
+    }
+
+    // Impulse of a row with effective `mass`, velocity error `vn` and
+    // velocity bias `bias`
+    pub fn impulse(self, mass: Real, vn: Real, bias: Real, accumulated: Real) -> Real {
+        -self.mass_scale * mass * (vn + bias) - self.impulse_scale * accumulated
+    }
+}
+
+impl Default for Softness {
+    fn default() -> Self {
+        Softness::RIGID
+    }
+}
+
+// Velocity bias of a one-sided limit with remaining slack `c`: positive slack
+// may be closed within the step, a violation is pushed out.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct LimitBias {
+    bias: Real,
+    softness: Softness,
+}
+
+impl LimitBias {
+    pub(crate) fn new(c: Real, bias_factor: Real, step: &StepContext) -> Self {
+        if c > 0.0 {
+            Self {
+                bias: c * step.inv_dt,
+                softness: Softness::RIGID,
+            }
+        } else {
+            let softness = step.rigid_softness(bias_factor);
+            Self {
+                bias: softness.bias_rate * c,
+                softness,
+            }
+        }
+    }
+
+    // Impulse pushing away from the limit, before clamping the accumulated
+    // impulse. Relaxing keeps the bias of positive slack, like contacts.
+    pub(crate) fn impulse(self, mass: Real, vn: Real, accumulated: Real, use_bias: bool) -> Real {
+        if use_bias {
+            self.softness.impulse(mass, vn, self.bias, accumulated)
+        } else {
+            -mass * (vn + Real::max(self.bias, real(0.0)))
+        }
+    }
+}
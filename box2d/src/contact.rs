@@ -1,4 +1,4 @@
-use crate::math_utils::Vec2;
+use crate::math_utils::{Real, Vec2};
 
 // Box vertex and edge numbering:
 //
@@ -41,13 +41,15 @@ pub struct Contact {
     pub normal: Vec2,
     pub r1: Vec2,
     pub r2: Vec2,
-    pub separation: f32,
-    pub pn: f32,  // accumulated normal impulse
-    pub pt: f32,  // accumulated tangent impulse
-    pub pnb: f32, // accumulated normal impulse for position bias
-    pub mass_normal: f32,
-    pub mass_tangent: f32,
-    pub bias: f32,
+    pub local_anchor1: Vec2, // contact point in body frames for the NGS pass
+    pub local_anchor2: Vec2,
+    pub separation: Real,
+    pub pn: Real,  // accumulated normal impulse
+    pub pt: Real,  // accumulated tangent impulse
+    pub pnb: Real, // accumulated normal impulse for position bias
+    pub mass_normal: Real,
+    pub mass_tangent: Real,
+    pub bias: Real,
     pub feature: Feature,
 }
 
@@ -0,0 +1,331 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, LimitBias, MAX_LINEAR_CORRECTION, Softness, StepContext};
+use crate::math_utils::{Mat22, Real, Vec2, clamp, cross_v_v, dot, real};
+use crate::solver_bodies::SolverBodies;
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Keeps two anchor points at a rest length. With `stiffness > 0` the rest
+// length is held by a spring, otherwise rigidly. `min_length < max_length`
+// turns on limits bounding the distance, and with `stiffness = 0` makes the
+// joint free between them (set `min_length = 0` to get a rope). They start
+// equal, so a new joint is rigid, or a spring once `stiffness` is set.
+pub struct DistanceJoint {
+    local_anchor1: Vec2,
+    local_anchor2: Vec2,
+    r1: Vec2,
+    r2: Vec2,
+    u: Vec2, // unit axis from anchor1 to anchor2
+    current_length: Real,
+    mass: Real,
+    soft_mass: Real,
+    gamma: Real,
+    bias: Real,
+    soft: Softness, // of the rigid length
+    lower_bias: LimitBias,
+    upper_bias: LimitBias,
+    impulse: Real,       // accumulated spring / rigid impulse
+    lower_impulse: Real, // accumulated min length impulse
+    upper_impulse: Real, // accumulated max length impulse
+    ids: (usize, usize),
+    body1: Rc<RefCell<Body>>,
+    body2: Rc<RefCell<Body>>,
+    pub length: Real,
+    pub min_length: Real,
+    pub max_length: Real,
+    pub stiffness: Real, // spring stiffness, 0 means rigid
+    pub damping: Real,   // spring damping
+    pub bias_factor: Real,
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
+}
+
+impl DistanceJoint {
+    pub fn new(
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor1: Vec2,
+        anchor2: Vec2,
+    ) -> Self {
+        let (local_anchor1, local_anchor2) = {
+            let body1 = &body1.borrow();
+            let body2 = &body2.borrow();
+
+            let rot1t = Mat22::from(body1.rot).transpose();
+            let rot2t = Mat22::from(body2.rot).transpose();
+
+            (
+                rot1t * (anchor1 - body1.position),
+                rot2t * (anchor2 - body2.position),
+            )
+        };
+        let length = (anchor2 - anchor1).length();
+
+        Self {
+            local_anchor1,
+            local_anchor2,
+            r1: Vec2::default(),
+            r2: Vec2::default(),
+            u: Vec2::default(),
+            current_length: length,
+            mass: real(0.0),
+            soft_mass: real(0.0),
+            gamma: real(0.0),
+            bias: real(0.0),
+            soft: Softness::RIGID,
+            lower_bias: LimitBias::default(),
+            upper_bias: LimitBias::default(),
+            impulse: real(0.0),
+            lower_impulse: real(0.0),
+            upper_impulse: real(0.0),
+            ids: (0, 0),
+            body1,
+            body2,
+            length,
+            min_length: length,
+            max_length: length,
+            stiffness: real(0.0),
+            damping: real(0.0),
+            bias_factor: real(0.2),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
+        }
+    }
+
+    pub fn current_length(&self) -> Real {
+        self.current_length
+    }
+
+    fn is_rigid(&self) -> bool {
+        self.stiffness <= 0.0 && !self.has_limits()
+    }
+
+    fn has_limits(&self) -> bool {
+        self.min_length < self.max_length
+    }
+
+    // Velocity of anchor2 relative to anchor1 along the joint axis
+    fn relative_velocity(&self, bodies: &SolverBodies) -> Real {
+        let (id1, id2) = self.ids;
+        let dv = bodies.velocity_at(id2, self.r2) - bodies.velocity_at(id1, self.r1);
+        dot(self.u, dv)
+    }
+
+    fn apply_axial_impulse(&self, bodies: &mut SolverBodies, impulse: Real) {
+        let (id1, id2) = self.ids;
+        let p = impulse * self.u;
+        bodies.sub_velocity(id1, self.r1, p);
+        bodies.add_velocity(id2, self.r2, p);
+    }
+}
+
+impl Constraint for DistanceJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        let inv_dt = step.inv_dt;
+        self.ids = (step.bodies.id(&self.body1), step.bodies.id(&self.body2));
+        let (id1, id2) = self.ids;
+        {
+            let bodies = &*step.bodies;
+            self.r1 = Mat22::from(bodies.rot(id1)) * self.local_anchor1;
+            self.r2 = Mat22::from(bodies.rot(id2)) * self.local_anchor2;
+
+            let d = (bodies.position(id2) + self.r2) - (bodies.position(id1) + self.r1);
+            self.current_length = d.length();
+            self.u = if self.current_length > Real::EPSILON {
+                (1.0 / self.current_length) * d
+            } else {
+                Vec2::default()
+            };
+
+            let cr1u = cross_v_v(self.r1, self.u);
+            let cr2u = cross_v_v(self.r2, self.u);
+            let inv_mass = bodies.inv_mass_along(id1, self.u)
+                + bodies.inv_mass_along(id2, self.u)
+                + bodies.angular_inv_i(id1) * cr1u * cr1u
+                + bodies.angular_inv_i(id2) * cr2u * cr2u;
+            self.mass = if inv_mass > 0.0 {
+                1.0 / inv_mass
+            } else {
+                real(0.0)
+            };
+
+            let c = self.current_length - self.length;
+            if self.stiffness > 0.0 && inv_dt > 0.0 {
+                // Soft constraint: impulse = -soft_mass * (vn + bias + gamma * impulse)
+                let dt = 1.0 / inv_dt;
+                let gamma = dt * (self.damping + dt * self.stiffness);
+                self.gamma = if gamma > 0.0 { 1.0 / gamma } else { real(0.0) };
+                self.bias = c * dt * self.stiffness * self.gamma;
+                let inv_soft_mass = inv_mass + self.gamma;
+                self.soft_mass = if inv_soft_mass > 0.0 {
+                    1.0 / inv_soft_mass
+                } else {
+                    real(0.0)
+                };
+            } else {
+                self.gamma = real(0.0);
+                self.soft = if step.solve_positions {
+                    Softness::RIGID
+                } else {
+                    step.rigid_softness(self.bias_factor)
+                };
+                self.bias = self.soft.bias_rate * c;
+                self.soft_mass = self.mass;
+            }
+
+            self.lower_bias = LimitBias::new(
+                self.current_length - self.min_length,
+                self.bias_factor,
+                step,
+            );
+            self.upper_bias = LimitBias::new(
+                self.max_length - self.current_length,
+                self.bias_factor,
+                step,
+            );
+        }
+
+        if !self.has_limits() {
+            self.lower_impulse = real(0.0);
+            self.upper_impulse = real(0.0);
+        }
+        if self.stiffness <= 0.0 && !self.is_rigid() {
+            self.impulse = real(0.0);
+        }
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            let impulse = self.impulse + self.lower_impulse - self.upper_impulse;
+            self.apply_axial_impulse(step.bodies, impulse);
+        } else {
+            self.impulse = real(0.0);
+            self.lower_impulse = real(0.0);
+            self.upper_impulse = real(0.0);
+        }
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let use_bias = step.use_bias;
+        if self.is_rigid() {
+            let vn = self.relative_velocity(step.bodies);
+            let impulse = if use_bias {
+                self.soft.impulse(self.mass, vn, self.bias, self.impulse)
+            } else {
+                -self.mass * vn
+            };
+            self.impulse += impulse;
+            self.apply_axial_impulse(step.bodies, impulse);
+            return;
+        }
+
+        if self.stiffness > 0.0 {
+            // Spring
+            let vn = self.relative_velocity(step.bodies);
+            let impulse = -self.soft_mass * (vn + self.bias + self.gamma * self.impulse);
+            self.impulse += impulse;
+            self.apply_axial_impulse(step.bodies, impulse);
+        }
+
+        if !self.has_limits() {
+            return;
+        }
+
+        // Lower limit
+        {
+            let vn = self.relative_velocity(step.bodies);
+            let impulse = self
+                .lower_bias
+                .impulse(self.mass, vn, self.lower_impulse, use_bias);
+            let old_impulse = self.lower_impulse;
+            self.lower_impulse = Real::max(real(0.0), old_impulse + impulse);
+            self.apply_axial_impulse(step.bodies, self.lower_impulse - old_impulse);
+        }
+
+        // Upper limit
+        {
+            let vn = -self.relative_velocity(step.bodies);
+            let impulse = self
+                .upper_bias
+                .impulse(self.mass, vn, self.upper_impulse, use_bias);
+            let old_impulse = self.upper_impulse;
+            self.upper_impulse = Real::max(real(0.0), old_impulse + impulse);
+            self.apply_axial_impulse(step.bodies, -(self.upper_impulse - old_impulse));
+        }
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        let impulse = self.impulse + self.lower_impulse - self.upper_impulse;
+        (inv_dt * impulse) * self.u
+    }
+
+    fn reaction_torque(&self, _inv_dt: Real) -> Real {
+        real(0.0)
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![self.body1.clone(), self.body2.clone()]
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    // Only the rigid length is corrected, springs and limits keep their bias
+    fn solve_position(&mut self, step: &mut StepContext) -> bool {
+        if !self.is_rigid() {
+            return true;
+        }
+        let linear_slop = step.linear_slop;
+        let (id1, id2) = self.ids;
+        let bodies = &mut *step.bodies;
+        let r1 = Mat22::from(bodies.rot(id1)) * self.local_anchor1;
+        let r2 = Mat22::from(bodies.rot(id2)) * self.local_anchor2;
+
+        let d = (bodies.position(id2) + r2) - (bodies.position(id1) + r1);
+        let length = d.length();
+        if length <= Real::EPSILON {
+            return true;
+        }
+        let u = (1.0 / length) * d;
+        let max_correction = MAX_LINEAR_CORRECTION * linear_slop;
+        let c = clamp(length - self.length, -max_correction, max_correction);
+
+        let ru1 = cross_v_v(r1, u);
+        let ru2 = cross_v_v(r2, u);
+        let k = bodies.inv_mass_along(id1, u)
+            + bodies.inv_mass_along(id2, u)
+            + bodies.angular_inv_i(id1) * ru1 * ru1
+            + bodies.angular_inv_i(id2) * ru2 * ru2;
+        let impulse = if k > 0.0 { -c / k } else { real(0.0) };
+
+        let p = impulse * u;
+        bodies.sub_position(id1, r1, p);
+        bodies.add_position(id2, r2, p);
+
+        c.abs() <= linear_slop
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        let b1 = self.body1.borrow();
+        let b2 = self.body2.borrow();
+        let x1 = b1.position;
+        let p1 = b1.transform() * self.local_anchor1;
+        let x2 = b2.position;
+        let p2 = b2.transform() * self.local_anchor2;
+
+        vec![(x1, p1), (p1, p2), (x2, p2)]
+    }
+}
@@ -0,0 +1,386 @@
+use core::cmp::Ordering;
+use core::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
+};
+
+// Q32.32 fixed-point number used as Real with the `fixed-point` feature.
+// Everything, trig and square root included, is integer arithmetic, so the
+// same inputs give the same bits on every platform. Overflow saturates.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i64);
+
+const FRAC_BITS: u32 = 32;
+const ONE_BITS: i64 = 1 << FRAC_BITS;
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(ONE_BITS);
+    pub const MAX: Fixed = Fixed(i64::MAX);
+    pub const MIN: Fixed = Fixed(i64::MIN);
+    // Smallest step, 2^-32
+    pub const EPSILON: Fixed = Fixed(1);
+    pub const PI: Fixed = Fixed(13_493_037_705);
+    const TAU: Fixed = Fixed(26_986_075_409);
+    const FRAC_PI_2: Fixed = Fixed(6_746_518_852);
+
+    pub const fn from_bits(bits: i64) -> Fixed {
+        Fixed(bits)
+    }
+
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    // Rounds to the nearest representable value, saturating out of range
+    pub const fn from_f64(x: f64) -> Fixed {
+        let scaled = x * ONE_BITS as f64;
+        let rounded = if scaled < 0.0 {
+            scaled - 0.5
+        } else {
+            scaled + 0.5
+        };
+        Fixed(rounded as i64)
+    }
+
+    pub const fn from_f32(x: f32) -> Fixed {
+        Fixed::from_f64(x as f64)
+    }
+
+    pub const fn from_int(x: i32) -> Fixed {
+        Fixed((x as i64) << FRAC_BITS)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / ONE_BITS as f64
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    pub fn abs(self) -> Fixed {
+        Fixed(self.0.saturating_abs())
+    }
+
+    pub fn signum(self) -> Fixed {
+        match self.0.cmp(&0) {
+            Ordering::Less => -Fixed::ONE,
+            Ordering::Equal => Fixed::ZERO,
+            Ordering::Greater => Fixed::ONE,
+        }
+    }
+
+    pub fn max(self, other: Fixed) -> Fixed {
+        Ord::max(self, other)
+    }
+
+    pub fn min(self, other: Fixed) -> Fixed {
+        Ord::min(self, other)
+    }
+
+    // Zero for negative input
+    pub fn sqrt(self) -> Fixed {
+        if self.0 <= 0 {
+            return Fixed::ZERO;
+        }
+        Fixed((((self.0 as u128) << FRAC_BITS).isqrt()) as i64)
+    }
+
+    pub fn sin(self) -> Fixed {
+        // Reduce to [-pi, pi], then to [-pi/2, pi/2] where the series converges fast
+        let mut x = self % Fixed::TAU;
+        if x > Fixed::PI {
+            x -= Fixed::TAU;
+        } else if x < -Fixed::PI {
+            x += Fixed::TAU;
+        }
+        if x > Fixed::FRAC_PI_2 {
+            x = Fixed::PI - x;
+        } else if x < -Fixed::FRAC_PI_2 {
+            x = -Fixed::PI - x;
+        }
+
+        // Taylor series, the x^17 term is below the resolution
+        let x2 = x * x;
+        let mut term = x;
+        let mut sum = x;
+        for k in 1..=8 {
+            term = -(term * x2) / Fixed::from_int((2 * k) * (2 * k + 1));
+            sum += term;
+        }
+        sum
+    }
+
+    pub fn cos(self) -> Fixed {
+        (self + Fixed::FRAC_PI_2).sin()
+    }
+
+    // Angle of the point (x, self) in [-pi, pi], zero at the origin
+    pub fn atan2(self, x: Fixed) -> Fixed {
+        let y = self;
+        if x.0 == 0 && y.0 == 0 {
+            return Fixed::ZERO;
+        }
+        if y.abs() <= x.abs() {
+            let angle = atan_unit(y / x);
+            if x.0 > 0 {
+                angle
+            } else if y.0 >= 0 {
+                angle + Fixed::PI
+            } else {
+                angle - Fixed::PI
+            }
+        } else {
+            let angle = atan_unit(x / y);
+            if y.0 > 0 {
+                Fixed::FRAC_PI_2 - angle
+            } else {
+                -Fixed::FRAC_PI_2 - angle
+            }
+        }
+    }
+}
+
+// atan on [-1, 1]
+fn atan_unit(x: Fixed) -> Fixed {
+    // Halve the angle twice, atan(x) = 2 atan(x / (1 + sqrt(1 + x^2))), so
+    // |x| <= tan(pi / 16) and the series converges fast
+    let mut x = x;
+    for _ in 0..2 {
+        x = x / (Fixed::ONE + (Fixed::ONE + x * x).sqrt());
+    }
+
+    let x2 = x * x;
+    let mut power = x;
+    let mut sum = x;
+    for k in 1..=8 {
+        power = -(power * x2);
+        sum += power / Fixed::from_int(2 * k + 1);
+    }
+    sum * Fixed::from_int(4)
+}
+
+fn saturate(x: i128) -> Fixed {
+    Fixed(x.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, other: Fixed) -> Fixed {
+        saturate((self.0 as i128 * other.0 as i128) >> FRAC_BITS)
+    }
+}
+
+// Division by zero saturates towards the sign of the dividend
+impl Div for Fixed {
+    type Output = Fixed;
+    fn div(self, other: Fixed) -> Fixed {
+        if other.0 == 0 {
+            return match self.0.cmp(&0) {
+                Ordering::Less => Fixed::MIN,
+                Ordering::Equal => Fixed::ZERO,
+                Ordering::Greater => Fixed::MAX,
+            };
+        }
+        saturate(((self.0 as i128) << FRAC_BITS) / other.0 as i128)
+    }
+}
+
+impl Rem for Fixed {
+    type Output = Fixed;
+    fn rem(self, other: Fixed) -> Fixed {
+        if other.0 == 0 {
+            return Fixed::ZERO;
+        }
+        Fixed(self.0.wrapping_rem(other.0))
+    }
+}
+
+macro_rules! assign_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for Fixed {
+            fn $method(&mut self, other: Fixed) {
+                *self = *self $op other;
+            }
+        }
+    };
+}
+
+assign_op!(AddAssign, add_assign, +);
+assign_op!(SubAssign, sub_assign, -);
+assign_op!(MulAssign, mul_assign, *);
+assign_op!(DivAssign, div_assign, /);
+assign_op!(RemAssign, rem_assign, %);
+
+// Float literals in the engine, like `0.5 * x` or `x > 0.0`, are f64 here
+macro_rules! f64_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<f64> for Fixed {
+            type Output = Fixed;
+            fn $method(self, other: f64) -> Fixed {
+                self $op Fixed::from_f64(other)
+            }
+        }
+
+        impl $trait<Fixed> for f64 {
+            type Output = Fixed;
+            fn $method(self, other: Fixed) -> Fixed {
+                Fixed::from_f64(self) $op other
+            }
+        }
+    };
+}
+
+f64_op!(Add, add, +);
+f64_op!(Sub, sub, -);
+f64_op!(Mul, mul, *);
+f64_op!(Div, div, /);
+
+impl PartialEq<f64> for Fixed {
+    fn eq(&self, other: &f64) -> bool {
+        *self == Fixed::from_f64(*other)
+    }
+}
+
+impl PartialOrd<f64> for Fixed {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        Some(self.cmp(&Fixed::from_f64(*other)))
+    }
+}
+
+impl From<f64> for Fixed {
+    fn from(x: f64) -> Fixed {
+        Fixed::from_f64(x)
+    }
+}
+
+impl core::fmt::Display for Fixed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.to_f64().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    fn fixed(x: f64) -> Fixed {
+        Fixed::from_f64(x)
+    }
+
+    #[test]
+    fn from_f64_rounds_to_nearest() {
+        let step = 1.0 / ONE_BITS as f64;
+        assert_eq!(fixed(1.4 * step), Fixed::from_bits(1));
+        assert_eq!(fixed(1.6 * step), Fixed::from_bits(2));
+        assert_eq!(fixed(-1.6 * step), Fixed::from_bits(-2));
+        assert_eq!(fixed(-0.75).to_f64(), -0.75);
+        assert_eq!(Fixed::from_int(-3), fixed(-3.0));
+        assert_eq!(fixed(1e12), Fixed::MAX);
+        assert_eq!(fixed(-1e12), Fixed::MIN);
+    }
+
+    #[test]
+    fn arithmetic_saturates() {
+        let big = fixed(2e9);
+        assert_eq!(Fixed::MAX + Fixed::EPSILON, Fixed::MAX);
+        assert_eq!(Fixed::MIN - Fixed::EPSILON, Fixed::MIN);
+        assert_eq!(-Fixed::MIN, Fixed::MAX);
+        assert_eq!(Fixed::MIN.abs(), Fixed::MAX);
+        assert_eq!(big * big, Fixed::MAX);
+        assert_eq!(big * -big, Fixed::MIN);
+        assert_eq!(big / fixed(0.25), Fixed::MAX);
+        assert_eq!(-big / fixed(0.25), Fixed::MIN);
+
+        // In range the results are exact or rounded down by one step
+        assert_eq!(fixed(1.5) * fixed(-2.25), fixed(-3.375));
+        assert_eq!(fixed(-3.375) / fixed(1.5), fixed(-2.25));
+        assert_eq!(fixed(1.0) / fixed(3.0), Fixed::from_bits(ONE_BITS / 3));
+        assert_eq!(fixed(7.5) % fixed(2.0), fixed(1.5));
+        assert_eq!(fixed(-7.5) % fixed(2.0), fixed(-1.5));
+
+        let mut x = fixed(1.0);
+        x += fixed(2.0);
+        x -= fixed(0.5);
+        x *= fixed(4.0);
+        x /= fixed(5.0);
+        x %= fixed(1.5);
+        assert_eq!(x, fixed(0.5));
+    }
+
+    #[test]
+    fn division_by_zero_saturates() {
+        assert_eq!(fixed(2.0) / Fixed::ZERO, Fixed::MAX);
+        assert_eq!(fixed(-2.0) / Fixed::ZERO, Fixed::MIN);
+        assert_eq!(Fixed::ZERO / Fixed::ZERO, Fixed::ZERO);
+        assert_eq!(fixed(2.0) % Fixed::ZERO, Fixed::ZERO);
+        assert_eq!(fixed(2.0) / 0.0, Fixed::MAX);
+    }
+
+    // The series is within a few steps. Fixed::TAU is 0.044 steps short of
+    // 2 pi, so range reduction adds that much error per turn.
+    fn trig_error_bound(x: f64) -> f64 {
+        let turns = x.abs() / core::f64::consts::TAU;
+        2e-9 + turns * 0.05 / ONE_BITS as f64
+    }
+
+    #[test]
+    fn sin_and_cos_error_bounds() {
+        // Densely over a few turns, then at every scale up to the largest value
+        let dense = (-1000..=1000).map(|i| i as f64 * 0.01);
+        let scales = (0..31).flat_map(|e| {
+            let x = (1u32 << e) as f64;
+            [x, 1.3 * x, 1.7 * x, -x, -1.3 * x, -1.7 * x]
+        });
+        for x in dense.chain(scales) {
+            let x = fixed(x);
+            let exact = x.to_f64();
+            let bound = trig_error_bound(exact);
+            let sin_error = (x.sin().to_f64() - exact.sin()).abs();
+            let cos_error = (x.cos().to_f64() - exact.cos()).abs();
+            assert!(sin_error <= bound, "sin({exact}): {sin_error} > {bound}");
+            assert!(cos_error <= bound, "cos({exact}): {cos_error} > {bound}");
+        }
+    }
+
+    #[test]
+    fn ops_with_f64_operands() {
+        let x = fixed(1.5);
+        assert_eq!(x + 0.5, fixed(2.0));
+        assert_eq!(0.5 + x, fixed(2.0));
+        assert_eq!(x - 2.0, fixed(-0.5));
+        assert_eq!(2.0 - x, fixed(0.5));
+        assert_eq!(x * 2.0, fixed(3.0));
+        assert_eq!(2.0 * x, fixed(3.0));
+        assert_eq!(x / 0.5, fixed(3.0));
+        assert_eq!(3.0 / x, fixed(2.0));
+        assert!(x == 1.5 && x != 1.0);
+        assert!(x > 1.0);
+        assert!(x < 2.0);
+        assert!(x >= 1.5);
+        assert!(x <= 1.5);
+        assert_eq!(Fixed::from(1.5), x);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::math_utils::{Real, Transform, real};
+use crate::world::World;
+use alloc::vec::Vec;
+
+// Steps the world with a fixed time step whatever the frame time is.
+// Frame time is accumulated and consumed in whole steps, at most `max_steps`
+// per frame, so a stalled frame does not hand the solver a huge dt. The
+// leftover fraction is exposed as `alpha` for interpolating between the
+// transforms before and after the last step.
+pub struct FixedStepper {
+    pub time_step: Real,
+    pub max_steps: u32,
+    accumulator: Real,
+    previous: Vec<Transform>,
+    current: Vec<Transform>,
+}
+
+impl FixedStepper {
+    pub fn new(time_step: Real, max_steps: u32) -> Self {
+        FixedStepper {
+            time_step,
+            max_steps,
+            accumulator: real(0.0),
+            previous: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    // Returns the number of steps taken
+    pub fn advance(&mut self, world: &mut World, frame_time: Real) -> u32 {
+        if self.time_step <= 0.0 {
+            return 0;
+        }
+        self.accumulator += frame_time.max(real(0.0));
+
+        let mut steps = 0;
+        while self.accumulator >= self.time_step && steps < self.max_steps {
+            Self::snapshot(world, &mut self.previous);
+            world.step(self.time_step);
+            self.accumulator -= self.time_step;
+            steps += 1;
+        }
+        if steps > 0 {
+            Self::snapshot(world, &mut self.current);
+        }
+        // Drop the time that could not be caught up on
+        if self.accumulator >= self.time_step {
+            self.accumulator %= self.time_step;
+        }
+        steps
+    }
+
+    // Fraction of a step left in the accumulator, in [0, 1)
+    pub fn alpha(&self) -> Real {
+        if self.time_step > 0.0 {
+            self.accumulator / self.time_step
+        } else {
+            real(0.0)
+        }
+    }
+
+    // Transforms before the last step, in World::get_bodies order
+    pub fn previous_transforms(&self) -> &[Transform] {
+        &self.previous
+    }
+
+    // Transforms after the last step, in World::get_bodies order
+    pub fn current_transforms(&self) -> &[Transform] {
+        &self.current
+    }
+
+    // Transform of the body at `index` blended by alpha. None for bodies
+    // added since the last step.
+    pub fn interpolated(&self, index: usize) -> Option<Transform> {
+        let previous = self.previous.get(index)?;
+        let current = self.current.get(index)?;
+        Some(previous.lerp(*current, self.alpha()))
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = real(0.0);
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    fn snapshot(world: &World, transforms: &mut Vec<Transform>) {
+        transforms.clear();
+        transforms.extend(world.get_bodies().iter().map(|body| {
+            let body = body.borrow();
+            body.transform()
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+    use crate::math_utils::Vec2;
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    // Step of a quarter second so the accumulator arithmetic is exact
+    const TIME_STEP: Real = real(0.25);
+
+    // Zero-gravity world with a unit box moving right at 1 and turning at 1
+    fn moving_body_world() -> World {
+        let mut world = World::new(Vec2::ZERO, 10);
+        let mut body = Body::new(real(1.0), real(1.0), real(1.0), real(0.0), real(0.0));
+        body.velocity = Vec2::new(real(1.0), real(0.0));
+        body.angular_velocity = real(1.0);
+        world.add_body(Rc::new(RefCell::new(body)));
+        world
+    }
+
+    #[test]
+    fn max_steps_caps_a_long_frame() {
+        let mut world = moving_body_world();
+        let mut stepper = FixedStepper::new(TIME_STEP, 3);
+        assert_eq!(stepper.advance(&mut world, real(10.125)), 3);
+        // The time that was not caught up on is dropped, not carried over
+        assert_eq!(stepper.alpha(), real(0.5));
+        assert_eq!(stepper.advance(&mut world, real(0.0)), 0);
+    }
+
+    #[test]
+    fn leftover_stays_below_one_step() {
+        let mut world = moving_body_world();
+        let mut stepper = FixedStepper::new(TIME_STEP, 5);
+        assert_eq!(stepper.advance(&mut world, real(0.625)), 2);
+        assert_eq!(stepper.alpha(), real(0.5));
+        assert_eq!(stepper.advance(&mut world, real(0.0625)), 0);
+        assert_eq!(stepper.alpha(), real(0.75));
+        // The leftover counts towards the next frame
+        assert_eq!(stepper.advance(&mut world, real(0.125)), 1);
+        assert_eq!(stepper.alpha(), real(0.25));
+        assert_eq!(stepper.advance(&mut world, real(-1.0)), 0);
+        assert_eq!(stepper.alpha(), real(0.25));
+    }
+
+    #[test]
+    fn alpha_stays_in_unit_interval() {
+        let mut world = moving_body_world();
+        let mut stepper = FixedStepper::new(real(1.0 / 60.0), 5);
+        let mut seed: u32 = 7;
+        for _ in 0..500 {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            let frame_time = real((seed >> 8) as f64 / (1u32 << 24) as f64 / 10.0);
+            stepper.advance(&mut world, frame_time);
+            let alpha = stepper.alpha();
+            assert!((real(0.0)..real(1.0)).contains(&alpha), "{alpha:?}");
+        }
+    }
+
+    #[test]
+    fn interpolated_blends_last_step() {
+        let mut world = moving_body_world();
+        let mut stepper = FixedStepper::new(TIME_STEP, 5);
+        assert_eq!(stepper.interpolated(0), None);
+        stepper.advance(&mut world, real(0.375));
+
+        let previous = stepper.previous_transforms()[0];
+        let current = stepper.current_transforms()[0];
+        assert_eq!(previous.position, Vec2::ZERO);
+        assert_eq!(current.position, Vec2::new(real(0.25), real(0.0)));
+
+        // Halfway through the next step
+        let blended = stepper.interpolated(0).unwrap();
+        assert_eq!(blended.position, Vec2::new(real(0.125), real(0.0)));
+        assert!((blended.rotation.angle() - real(0.125)).abs() < real(1e-4));
+
+        // Bodies added since the last step have nothing to blend
+        let body = Body::new(real(1.0), real(1.0), real(1.0), real(3.0), real(0.0));
+        world.add_body(Rc::new(RefCell::new(body)));
+        assert_eq!(stepper.interpolated(1), None);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, Softness, StepContext};
+use crate::math_utils::{Mat22, Real, Vec2, cross_v_v, dot, real, unwind_angle};
+use crate::prismatic_joint::PrismaticJoint;
+use crate::revolute_joint::RevoluteJoint;
+use crate::solver_bodies::SolverBodies;
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// A joint whose coordinate (angle or translation) a gear joint couples
+pub enum GearConnection {
+    Revolute(Rc<RefCell<RevoluteJoint>>),
+    Prismatic(Rc<RefCell<PrismaticJoint>>),
+}
+
+impl GearConnection {
+    fn joint(&self) -> Rc<RefCell<dyn Constraint>> {
+        match self {
+            GearConnection::Revolute(joint) => joint.clone(),
+            GearConnection::Prismatic(joint) => joint.clone(),
+        }
+    }
+}
+
+impl From<Rc<RefCell<RevoluteJoint>>> for GearConnection {
+    fn from(joint: Rc<RefCell<RevoluteJoint>>) -> Self {
+        GearConnection::Revolute(joint)
+    }
+}
+
+impl From<Rc<RefCell<PrismaticJoint>>> for GearConnection {
+    fn from(joint: Rc<RefCell<PrismaticJoint>>) -> Self {
+        GearConnection::Prismatic(joint)
+    }
+}
+
+// Body2 of a connected joint moving against its body1 ("ground").
+struct GearSide {
+    body: Rc<RefCell<Body>>,
+    ground: Rc<RefCell<Body>>,
+    local_anchor: Vec2,
+    local_ground_anchor: Vec2,
+    local_axis: Option<Vec2>, // slide axis in ground frame, None for revolute
+    reference_angle: Real,
+    // Joint angle followed through full turns, body angles wrap at pi
+    angle: Real,
+    ids: (usize, usize), // of body and ground
+    u: Vec2,
+    r: Vec2,
+    ground_r: Vec2,
+}
+
+impl GearSide {
+    fn new(connection: &GearConnection) -> Self {
+        match connection {
+            GearConnection::Revolute(joint) => {
+                let joint = joint.borrow();
+                Self::with(
+                    joint.point.body2.clone(),
+                    joint.point.body1.clone(),
+                    joint.point.local_anchor2,
+                    joint.point.local_anchor1,
+                    None,
+                    joint.reference_angle,
+                )
+            }
+            GearConnection::Prismatic(joint) => {
+                let joint = joint.borrow();
+                Self::with(
+                    joint.body2.clone(),
+                    joint.body1.clone(),
+                    joint.local_anchor2,
+                    joint.local_anchor1,
+                    Some(joint.local_axis),
+                    real(0.0),
+                )
+            }
+        }
+    }
+
+    fn with(
+        body: Rc<RefCell<Body>>,
+        ground: Rc<RefCell<Body>>,
+        local_anchor: Vec2,
+        local_ground_anchor: Vec2,
+        local_axis: Option<Vec2>,
+        reference_angle: Real,
+    ) -> Self {
+        Self {
+            body,
+            ground,
+            local_anchor,
+            local_ground_anchor,
+            local_axis,
+            reference_angle,
+            angle: real(0.0),
+            ids: (0, 0),
+            u: Vec2::default(),
+            r: Vec2::default(),
+            ground_r: Vec2::default(),
+        }
+    }
+
+    fn find_ids(&mut self, bodies: &mut SolverBodies) {
+        self.ids = (bodies.id(&self.body), bodies.id(&self.ground));
+    }
+
+    // Joint angle for a revolute joint, translation for a prismatic joint
+    fn coordinate(&mut self, bodies: &SolverBodies) -> Real {
+        let (body, ground) = self.ids;
+        match self.local_axis {
+            None => {
+                let angle = bodies.rotation(body) - bodies.rotation(ground) - self.reference_angle;
+                self.angle += unwind_angle(angle - self.angle);
+                self.angle
+            }
+            Some(local_axis) => {
+                let rot = Mat22::from(bodies.rot(body));
+                let ground_rot_t = Mat22::from(bodies.rot(ground)).transpose();
+                let p = ground_rot_t
+                    * ((rot * self.local_anchor)
+                        + (bodies.position(body) - bodies.position(ground)));
+                dot(p - self.local_ground_anchor, local_axis)
+            }
+        }
+    }
+
+    // Pre-compute the Jacobian and return the inverse effective mass
+    fn prepare(&mut self, bodies: &SolverBodies) -> Real {
+        let (body, ground) = self.ids;
+        match self.local_axis {
+            None => bodies.angular_inv_i(body) + bodies.angular_inv_i(ground),
+            Some(local_axis) => {
+                let ground_rot = Mat22::from(bodies.rot(ground));
+                self.u = ground_rot * local_axis;
+                self.r = Mat22::from(bodies.rot(body)) * self.local_anchor;
+                self.ground_r = ground_rot * self.local_ground_anchor;
+                let jw = cross_v_v(self.r, self.u);
+                let ground_jw = cross_v_v(self.ground_r, self.u);
+                bodies.inv_mass_along(body, self.u)
+                    + bodies.inv_mass_along(ground, self.u)
+                    + bodies.angular_inv_i(body) * jw * jw
+                    + bodies.angular_inv_i(ground) * ground_jw * ground_jw
+            }
+        }
+    }
+
+    // Rate of change of the coordinate
+    fn velocity(&self, bodies: &SolverBodies) -> Real {
+        let (body, ground) = self.ids;
+        match self.local_axis {
+            None => bodies.angular_velocity(body) - bodies.angular_velocity(ground),
+            Some(_) => {
+                let v = bodies.velocity_at(body, self.r);
+                let ground_v = bodies.velocity_at(ground, self.ground_r);
+                dot(self.u, v - ground_v)
+            }
+        }
+    }
+
+    fn apply(&self, bodies: &mut SolverBodies, impulse: Real) {
+        let (body, ground) = self.ids;
+        match self.local_axis {
+            None => {
+                bodies.apply_angular_impulse(body, impulse);
+                bodies.apply_angular_impulse(ground, -impulse);
+            }
+            Some(_) => {
+                bodies.add_velocity(body, self.r, impulse * self.u);
+                bodies.sub_velocity(ground, self.ground_r, impulse * self.u);
+            }
+        }
+    }
+}
+
+// Couples two revolute or prismatic joints:
+// coordinate1 + ratio * coordinate2 stays constant.
+// It breaks when either joint breaks or is removed from World.
+pub struct GearJoint {
+    joints: [Rc<RefCell<dyn Constraint>>; 2],
+    side1: GearSide,
+    side2: GearSide,
+    constant: Real,
+    mass: Real,
+    bias: Real,
+    soft: Softness,
+    impulse: Real, // accumulated impulse
+    pub ratio: Real,
+    pub bias_factor: Real,
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
+}
+
+impl GearJoint {
+    pub fn new(joint1: GearConnection, joint2: GearConnection, ratio: Real) -> Self {
+        let mut side1 = GearSide::new(&joint1);
+        let mut side2 = GearSide::new(&joint2);
+        // Read the bodies through the same view the solver uses
+        let mut bodies = SolverBodies::default();
+        side1.find_ids(&mut bodies);
+        side2.find_ids(&mut bodies);
+        let constant = side1.coordinate(&bodies) + ratio * side2.coordinate(&bodies);
+
+        Self {
+            joints: [joint1.joint(), joint2.joint()],
+            side1,
+            side2,
+            constant,
+            mass: real(0.0),
+            bias: real(0.0),
+            soft: Softness::RIGID,
+            impulse: real(0.0),
+            ratio,
+            bias_factor: real(0.2),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
+        }
+    }
+
+    fn apply(&self, bodies: &mut SolverBodies, impulse: Real) {
+        self.side1.apply(bodies, impulse);
+        self.side2.apply(bodies, self.ratio * impulse);
+    }
+}
+
+impl Constraint for GearJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        self.side1.find_ids(step.bodies);
+        self.side2.find_ids(step.bodies);
+        let k = self.side1.prepare(step.bodies)
+            + self.ratio * self.ratio * self.side2.prepare(step.bodies);
+        self.mass = if k > 0.0 { 1.0 / k } else { real(0.0) };
+
+        self.soft = step.rigid_softness(self.bias_factor);
+        let c = self.side1.coordinate(step.bodies)
+            + self.ratio * self.side2.coordinate(step.bodies)
+            - self.constant;
+        self.bias = self.soft.bias_rate * c;
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            self.apply(step.bodies, self.impulse);
+        } else {
+            self.impulse = real(0.0);
+        }
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let vn = self.side1.velocity(step.bodies) + self.ratio * self.side2.velocity(step.bodies);
+        let impulse = if step.use_bias {
+            self.soft.impulse(self.mass, vn, self.bias, self.impulse)
+        } else {
+            -self.mass * vn
+        };
+        self.impulse += impulse;
+        self.apply(step.bodies, impulse);
+    }
+
+    // Reported for the body of joint1
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        match self.side1.local_axis {
+            None => Vec2::default(),
+            Some(_) => (inv_dt * self.impulse) * self.side1.u,
+        }
+    }
+
+    fn reaction_torque(&self, inv_dt: Real) -> Real {
+        match self.side1.local_axis {
+            None => inv_dt * self.impulse,
+            Some(_) => inv_dt * self.impulse * cross_v_v(self.side1.r, self.side1.u),
+        }
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![
+            self.side1.ground.clone(),
+            self.side1.body.clone(),
+            self.side2.ground.clone(),
+            self.side2.body.clone(),
+        ]
+    }
+
+    // Only the geared bodies, their grounds may still collide with them
+    fn connected_bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![self.side1.body.clone(), self.side2.body.clone()]
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    fn coupled_joints(&self) -> Vec<Rc<RefCell<dyn Constraint>>> {
+        self.joints.to_vec()
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        vec![(
+            self.side1.body.borrow().position,
+            self.side2.body.borrow().position,
+        )]
+    }
+}
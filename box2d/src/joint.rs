@@ -1,20 +1,30 @@
 use crate::body::Body;
-use crate::math_utils::{Mat22, Vec2, cross_f_v};
-use crate::world::{POSITION_CORRECTION, WARM_STARTING};
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::constraint::{Constraint, Softness, StepContext};
+use crate::math_utils::{Mat22, Real, Vec2, real};
+use crate::solver_bodies::SolverBodies;
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
 pub struct Joint {
     m: Mat22,
-    local_anchor1: Vec2,
-    local_anchor2: Vec2,
+    pub(crate) local_anchor1: Vec2,
+    pub(crate) local_anchor2: Vec2,
     r1: Vec2,
     r2: Vec2,
     bias: Vec2,
+    soft: Softness,
     p: Vec2, // accumulated impulse
-    body1: Rc<RefCell<Body>>,
-    body2: Rc<RefCell<Body>>,
-    pub bias_factor: f32,
-    pub softness: f32,
+    // Solver ids of body1 and body2 in the current step
+    pub(crate) ids: (usize, usize),
+    pub(crate) body1: Rc<RefCell<Body>>,
+    pub(crate) body2: Rc<RefCell<Body>>,
+    pub bias_factor: Real,
+    pub softness: Real,
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
 }
 
 impl Joint {
@@ -23,8 +33,8 @@ impl Joint {
             let body1 = &body1.borrow();
             let body2 = &body2.borrow();
 
-            let rot1 = Mat22::from_angle(body1.rotation);
-            let rot2 = Mat22::from_angle(body2.rotation);
+            let rot1 = Mat22::from(body1.rot);
+            let rot2 = Mat22::from(body2.rot);
             let rot1t = rot1.transpose();
             let rot2t = rot2.transpose();
 
@@ -41,101 +51,158 @@ impl Joint {
             r1: Vec2::default(),
             r2: Vec2::default(),
             bias: Vec2::default(),
+            soft: Softness::RIGID,
             p: Vec2::default(), // accumulated impulse
-            body1: body1,
-            body2: body2,
-            bias_factor: 0.2,
-            softness: 0.0,
+            ids: (0, 0),
+            body1,
+            body2,
+            bias_factor: real(0.2),
+            softness: real(0.0),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
         }
     }
+}
 
-    pub fn get_joint_lines(&self) -> [(Vec2, Vec2); 2] {
-        let b1 = self.body1.borrow();
-        let b2 = self.body2.borrow();
-        let r1 = Mat22::from_angle(b1.rotation);
-        let r2 = Mat22::from_angle(b2.rotation);
-        let x1 = b1.position;
-        let p1 = x1 + (r1 * self.local_anchor1);
-        let x2 = b2.position;
-        let p2 = x2 + (r2 * self.local_anchor2);
-
-        [(x1, p1), (x2, p2)]
-    }
-
-    pub fn pre_step(&mut self, inv_dt: f32) {
+impl Constraint for Joint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        self.ids = (step.bodies.id(&self.body1), step.bodies.id(&self.body2));
+        let (id1, id2) = self.ids;
         {
-            let body1 = &self.body1.borrow();
-            let body2 = &self.body2.borrow();
+            let bodies = &*step.bodies;
             // Pre-compute anchors, mass matrix, and bias.
-            self.r1 = Mat22::from_angle(body1.rotation) * self.local_anchor1;
-            self.r2 = Mat22::from_angle(body2.rotation) * self.local_anchor2;
-
-            // deltaV = deltaV0 + K * impulse
-            // invM = [(1/m1 + 1/m2) * eye(2) - skew(r1) * invI1 * skew(r1) - skew(r2) * invI2 * skew(r2)]
-            //      = [1/m1+1/m2     0    ] + invI1 * [r1.y*r1.y -r1.x*r1.y] + invI2 * [r1.y*r1.y -r1.x*r1.y]
-            //        [    0     1/m1+1/m2]           [-r1.x*r1.y r1.x*r1.x]           [-r1.x*r1.y r1.x*r1.x]
-            let mut k1 = Mat22::default();
-            (k1.col1.x, k1.col2.x) = (body1.inv_mass + body2.inv_mass, 0.0);
-            (k1.col1.y, k1.col2.y) = (0.0, body1.inv_mass + body2.inv_mass);
-
-            let mut k2 = Mat22::default();
-            (k2.col1.x, k2.col2.x) = (
-                body1.inv_i * self.r1.y * self.r1.y,
-                -body1.inv_i * self.r1.x * self.r1.y,
-            );
-            (k2.col1.y, k2.col2.y) = (
-                -body1.inv_i * self.r1.x * self.r1.y,
-                body1.inv_i * self.r1.x * self.r1.x,
-            );
-
-            let mut k3 = Mat22::default();
-            (k3.col1.x, k3.col2.x) = (
-                body2.inv_i * self.r2.y * self.r2.y,
-                -body2.inv_i * self.r2.x * self.r2.y,
-            );
-            (k3.col1.y, k3.col2.y) = (
-                -body2.inv_i * self.r2.x * self.r2.y,
-                body2.inv_i * self.r2.x * self.r2.x,
-            );
-
-            let mut k = (k1 + k2) + k3;
+            self.r1 = Mat22::from(bodies.rot(id1)) * self.local_anchor1;
+            self.r2 = Mat22::from(bodies.rot(id2)) * self.local_anchor2;
+
+            let mut k = mass_matrix(bodies, self.ids, self.r1, self.r2);
             k.col1.x += self.softness;
             k.col2.y += self.softness;
 
             self.m = k.invert();
 
-            let p1 = body1.position + self.r1;
-            let p2 = body2.position + self.r2;
+            let p1 = bodies.position(id1) + self.r1;
+            let p2 = bodies.position(id2) + self.r2;
             let dp = p2 - p1;
 
-            if POSITION_CORRECTION {
-                self.bias = -self.bias_factor * inv_dt * dp;
+            // NGS corrects rigid joints on positions, soft ones keep the bias.
+            // The soft step does not soften joints that have own softness.
+            self.soft = if self.softness > 0.0 {
+                step.baumgarte(self.bias_factor)
+            } else if step.solve_positions {
+                Softness::RIGID
             } else {
-                self.bias.set(0.0, 0.0);
-            }
+                step.rigid_softness(self.bias_factor)
+            };
+            self.bias = -self.soft.bias_rate * dp;
         }
 
         if WARM_STARTING {
             // Apply accumulated impulse.
-            self.body1.borrow_mut().sub_velocity(self.r1, self.p);
-            self.body2.borrow_mut().add_velocity(self.r2, self.p);
+            step.bodies.sub_velocity(id1, self.r1, self.p);
+            step.bodies.add_velocity(id2, self.r2, self.p);
         } else {
-            self.p.set(0.0, 0.0);
+            self.p.set(real(0.0), real(0.0));
         }
     }
 
-    pub fn apply_impulse(&mut self) {
-        let impulse = {
-            let body1 = self.body1.borrow();
-            let body2 = self.body2.borrow();
-            let dv = (body2.velocity + cross_f_v(body2.angular_velocity, self.r2))
-                - (body1.velocity + cross_f_v(body1.angular_velocity, self.r1));
-
-            self.m * ((self.bias - dv) - (self.softness * self.p))
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let (id1, id2) = self.ids;
+        let bodies = &mut *step.bodies;
+        let dv = bodies.velocity_at(id2, self.r2) - bodies.velocity_at(id1, self.r1);
+        let impulse = if step.use_bias {
+            let impulse = self.m * ((self.bias - dv) - (self.softness * self.p));
+            self.soft.mass_scale * impulse - self.soft.impulse_scale * self.p
+        } else {
+            self.m * (-dv - (self.softness * self.p))
         };
 
-        self.body1.borrow_mut().sub_velocity(self.r1, impulse);
-        self.body2.borrow_mut().add_velocity(self.r2, impulse);
+        bodies.sub_velocity(id1, self.r1, impulse);
+        bodies.add_velocity(id2, self.r2, impulse);
         self.p += impulse;
     }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        inv_dt * self.p
+    }
+
+    fn reaction_torque(&self, _inv_dt: Real) -> Real {
+        real(0.0)
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![self.body1.clone(), self.body2.clone()]
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    // Soft joints are left to their velocity bias, correcting them here
+    // would make them rigid
+    fn solve_position(&mut self, step: &mut StepContext) -> bool {
+        if self.softness > 0.0 {
+            return true;
+        }
+        let (id1, id2) = self.ids;
+        let bodies = &mut *step.bodies;
+        let r1 = Mat22::from(bodies.rot(id1)) * self.local_anchor1;
+        let r2 = Mat22::from(bodies.rot(id2)) * self.local_anchor2;
+
+        let dp = (bodies.position(id2) + r2) - (bodies.position(id1) + r1);
+        let impulse = mass_matrix(bodies, self.ids, r1, r2).invert() * -dp;
+        bodies.sub_position(id1, r1, impulse);
+        bodies.add_position(id2, r2, impulse);
+
+        dp.length() <= step.linear_slop
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        let b1 = self.body1.borrow();
+        let b2 = self.body2.borrow();
+        let x1 = b1.position;
+        let p1 = b1.transform() * self.local_anchor1;
+        let x2 = b2.position;
+        let p2 = b2.transform() * self.local_anchor2;
+
+        vec![(x1, p1), (x2, p2)]
+    }
+}
+
+// deltaV = deltaV0 + K * impulse
+// invM = [(1/m1 + 1/m2) * eye(2) - skew(r1) * invI1 * skew(r1) - skew(r2) * invI2 * skew(r2)]
+//      = [1/m1+1/m2     0    ] + invI1 * [r1.y*r1.y -r1.x*r1.y] + invI2 * [r1.y*r1.y -r1.x*r1.y]
+//        [    0     1/m1+1/m2]           [-r1.x*r1.y r1.x*r1.x]           [-r1.x*r1.y r1.x*r1.x]
+pub(crate) fn mass_matrix(
+    bodies: &SolverBodies,
+    (id1, id2): (usize, usize),
+    r1: Vec2,
+    r2: Vec2,
+) -> Mat22 {
+    let inv_mass1 = bodies.linear_inv_mass(id1);
+    let inv_mass2 = bodies.linear_inv_mass(id2);
+    let inv_i1 = bodies.angular_inv_i(id1);
+    let inv_i2 = bodies.angular_inv_i(id2);
+
+    let mut k1 = Mat22::default();
+    (k1.col1.x, k1.col2.x) = (inv_mass1.x + inv_mass2.x, real(0.0));
+    (k1.col1.y, k1.col2.y) = (real(0.0), inv_mass1.y + inv_mass2.y);
+
+    let mut k2 = Mat22::default();
+    (k2.col1.x, k2.col2.x) = (inv_i1 * r1.y * r1.y, -inv_i1 * r1.x * r1.y);
+    (k2.col1.y, k2.col2.y) = (-inv_i1 * r1.x * r1.y, inv_i1 * r1.x * r1.x);
+
+    let mut k3 = Mat22::default();
+    (k3.col1.x, k3.col2.x) = (inv_i2 * r2.y * r2.y, -inv_i2 * r2.x * r2.y);
+    (k3.col1.y, k3.col2.y) = (-inv_i2 * r2.x * r2.y, inv_i2 * r2.x * r2.x);
+
+    (k1 + k2) + k3
 }
@@ -1,12 +1,44 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod arbiter;
 mod body;
 mod collide;
+mod constraint;
 mod contact;
+mod distance_joint;
+#[cfg(feature = "fixed-point")]
+mod fixed;
+mod fixed_stepper;
+mod gear_joint;
 mod joint;
-mod math_utils;
+pub mod math_utils;
+mod motor_joint;
+mod mouse_joint;
+mod prismatic_joint;
+mod pulley_joint;
+mod revolute_joint;
+mod solver_bodies;
+mod weld_joint;
+mod wheel_joint;
 mod world;
 
 pub use body::{Body, UNMOVABLE_MASS};
+pub use constraint::{Constraint, Softness, StepContext};
+pub use distance_joint::DistanceJoint;
+#[cfg(feature = "fixed-point")]
+pub use fixed::Fixed;
+pub use fixed_stepper::FixedStepper;
+pub use gear_joint::{GearConnection, GearJoint};
 pub use joint::Joint;
-pub use math_utils::Vec2;
-pub use world::World;
+pub use math_utils::{Aabb, Mat22, PI, Real, Rot, Transform, Vec2, atan2, cos, real, sin, sqrt};
+pub use motor_joint::MotorJoint;
+pub use mouse_joint::MouseJoint;
+pub use prismatic_joint::PrismaticJoint;
+pub use pulley_joint::PulleyJoint;
+pub use revolute_joint::RevoluteJoint;
+pub use solver_bodies::SolverBodies;
+pub use weld_joint::WeldJoint;
+pub use wheel_joint::WheelJoint;
+pub use world::{PositionCorrection, SolverMode, World};
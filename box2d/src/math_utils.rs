@@ -1,15 +1,50 @@
-#[derive(Debug, Copy, Clone, Default)]
+// Scalar type of the engine: f32 by default, f64 with the `f64` feature and
+// Q32.32 fixed point with `fixed-point`
+#[cfg(not(any(feature = "f64", feature = "fixed-point")))]
+pub type Real = f32;
+#[cfg(all(feature = "f64", not(feature = "fixed-point")))]
+pub type Real = f64;
+#[cfg(feature = "fixed-point")]
+pub type Real = crate::fixed::Fixed;
+
+#[cfg(all(feature = "f64", feature = "fixed-point"))]
+compile_error!("features `f64` and `fixed-point` are mutually exclusive");
+
+#[cfg(not(any(feature = "f64", feature = "fixed-point")))]
+pub use core::f32::consts::PI;
+#[cfg(all(feature = "f64", not(feature = "fixed-point")))]
+pub use core::f64::consts::PI;
+#[cfg(feature = "fixed-point")]
+pub const PI: Real = Real::PI;
+
+// Spells a constant as Real. Plain float literals only work where a fixed-point
+// Real can take them as f64 operands.
+#[cfg(not(feature = "fixed-point"))]
+pub const fn real(x: f64) -> Real {
+    x as Real
+}
+
+#[cfg(feature = "fixed-point")]
+pub const fn real(x: f64) -> Real {
+    Real::from_f64(x)
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct Vec2 {
-    pub x: f32,
-    pub y: f32,
+    pub x: Real,
+    pub y: Real,
 }
 
 impl Vec2 {
-    pub fn new(x: f32, y: f32) -> Self {
+    pub const ZERO: Vec2 = Vec2::new(real(0.0), real(0.0));
+    pub const X: Vec2 = Vec2::new(real(1.0), real(0.0));
+    pub const Y: Vec2 = Vec2::new(real(0.0), real(1.0));
+
+    pub const fn new(x: Real, y: Real) -> Self {
         Self { x, y }
     }
 
-    pub fn set(&mut self, x: f32, y: f32) {
+    pub fn set(&mut self, x: Real, y: Real) {
         self.x = x;
         self.y = y;
     }
@@ -17,87 +52,298 @@ impl Vec2 {
     pub fn abs(self) -> Self {
         Self::new(self.x.abs(), self.y.abs())
     }
+
+    pub fn length(self) -> Real {
+        sqrt(self.x * self.x + self.y * self.y)
+    }
+
+    pub fn length_squared(self) -> Real {
+        dot(self, self)
+    }
+
+    // Unit vector in the same direction, zero for a zero vector
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length > 0.0 {
+            self / length
+        } else {
+            Vec2::ZERO
+        }
+    }
+
+    pub fn distance(self, other: Vec2) -> Real {
+        (other - self).length()
+    }
+
+    pub fn dot(self, other: Vec2) -> Real {
+        dot(self, other)
+    }
+
+    pub fn cross(self, other: Vec2) -> Real {
+        cross_v_v(self, other)
+    }
+
+    // Rotated by 90 degrees counterclockwise
+    pub fn perp(self) -> Self {
+        Vec2::new(-self.y, self.x)
+    }
+
+    pub fn lerp(self, other: Vec2, alpha: Real) -> Self {
+        self + alpha * (other - self)
+    }
+
+    pub fn min(self, other: Vec2) -> Self {
+        Vec2::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    pub fn max(self, other: Vec2) -> Self {
+        Vec2::new(self.x.max(other.x), self.y.max(other.y))
+    }
+}
+
+impl From<[Real; 2]> for Vec2 {
+    fn from([x, y]: [Real; 2]) -> Self {
+        Vec2::new(x, y)
+    }
+}
+
+impl From<(Real, Real)> for Vec2 {
+    fn from((x, y): (Real, Real)) -> Self {
+        Vec2::new(x, y)
+    }
+}
+
+impl From<Vec2> for [Real; 2] {
+    fn from(v: Vec2) -> Self {
+        [v.x, v.y]
+    }
+}
+
+impl From<Vec2> for (Real, Real) {
+    fn from(v: Vec2) -> Self {
+        (v.x, v.y)
+    }
 }
 
-impl std::ops::AddAssign for Vec2 {
+impl core::ops::AddAssign for Vec2 {
     fn add_assign(&mut self, other: Vec2) {
         self.x += other.x;
         self.y += other.y;
     }
 }
 
-impl std::ops::SubAssign for Vec2 {
+impl core::ops::SubAssign for Vec2 {
     fn sub_assign(&mut self, other: Vec2) {
         self.x -= other.x;
         self.y -= other.y;
     }
 }
 
-impl std::ops::Add for Vec2 {
+impl core::ops::Add for Vec2 {
     type Output = Vec2;
     fn add(self, other: Vec2) -> Self::Output {
         Vec2::new(self.x + other.x, self.y + other.y)
     }
 }
 
-impl std::ops::Sub for Vec2 {
+impl core::ops::Sub for Vec2 {
     type Output = Vec2;
     fn sub(self, other: Vec2) -> Self::Output {
         Vec2::new(self.x - other.x, self.y - other.y)
     }
 }
 
-pub fn dot(a: Vec2, b: Vec2) -> f32 {
+// Component-wise
+impl core::ops::Mul for Vec2 {
+    type Output = Vec2;
+    fn mul(self, other: Vec2) -> Self::Output {
+        Vec2::new(self.x * other.x, self.y * other.y)
+    }
+}
+
+impl core::ops::Div<Real> for Vec2 {
+    type Output = Vec2;
+    fn div(self, s: Real) -> Self::Output {
+        Vec2::new(self.x / s, self.y / s)
+    }
+}
+
+impl core::ops::MulAssign<Real> for Vec2 {
+    fn mul_assign(&mut self, s: Real) {
+        self.x *= s;
+        self.y *= s;
+    }
+}
+
+impl core::ops::DivAssign<Real> for Vec2 {
+    fn div_assign(&mut self, s: Real) {
+        self.x /= s;
+        self.y /= s;
+    }
+}
+
+// By-reference forms of the binary operators
+macro_rules! ref_ops {
+    ($($trait:ident, $method:ident, $rhs:ty);* $(;)?) => {
+        $(
+            impl core::ops::$trait<&$rhs> for Vec2 {
+                type Output = Vec2;
+                fn $method(self, other: &$rhs) -> Self::Output {
+                    core::ops::$trait::$method(self, *other)
+                }
+            }
+
+            impl core::ops::$trait<$rhs> for &Vec2 {
+                type Output = Vec2;
+                fn $method(self, other: $rhs) -> Self::Output {
+                    core::ops::$trait::$method(*self, other)
+                }
+            }
+
+            impl core::ops::$trait<&$rhs> for &Vec2 {
+                type Output = Vec2;
+                fn $method(self, other: &$rhs) -> Self::Output {
+                    core::ops::$trait::$method(*self, *other)
+                }
+            }
+        )*
+    };
+}
+
+ref_ops! {
+    Add, add, Vec2;
+    Sub, sub, Vec2;
+    Mul, mul, Vec2;
+    Mul, mul, Real;
+    Div, div, Real;
+}
+
+impl core::ops::Neg for &Vec2 {
+    type Output = Vec2;
+    fn neg(self) -> Self::Output {
+        -*self
+    }
+}
+
+impl core::iter::Sum for Vec2 {
+    fn sum<I: Iterator<Item = Vec2>>(iter: I) -> Self {
+        iter.fold(Vec2::ZERO, |sum, v| sum + v)
+    }
+}
+
+pub use scalar_math::{atan2, cos, sin, sqrt};
+
+// Fixed point brings its own trig and square root
+#[cfg(feature = "fixed-point")]
+mod scalar_math {
+    use super::Real;
+
+    pub fn sin(x: Real) -> Real {
+        x.sin()
+    }
+
+    pub fn cos(x: Real) -> Real {
+        x.cos()
+    }
+
+    pub fn sqrt(x: Real) -> Real {
+        x.sqrt()
+    }
+
+    pub fn atan2(y: Real, x: Real) -> Real {
+        y.atan2(x)
+    }
+}
+
+// With the `libm` feature floats go through libm, which is the same bit for
+// bit on every platform and needs no std. std may use the platform libm.
+#[cfg(all(feature = "libm", not(feature = "fixed-point"), not(feature = "f64")))]
+mod scalar_math {
+    pub use libm::{atan2f as atan2, cosf as cos, sinf as sin, sqrtf as sqrt};
+}
+
+#[cfg(all(feature = "libm", not(feature = "fixed-point"), feature = "f64"))]
+mod scalar_math {
+    pub use libm::{atan2, cos, sin, sqrt};
+}
+
+#[cfg(all(feature = "std", not(any(feature = "libm", feature = "fixed-point"))))]
+mod scalar_math {
+    use super::Real;
+
+    pub fn sin(x: Real) -> Real {
+        Real::sin(x)
+    }
+
+    pub fn cos(x: Real) -> Real {
+        Real::cos(x)
+    }
+
+    pub fn sqrt(x: Real) -> Real {
+        Real::sqrt(x)
+    }
+
+    pub fn atan2(y: Real, x: Real) -> Real {
+        Real::atan2(y, x)
+    }
+}
+
+#[cfg(not(any(feature = "std", feature = "libm", feature = "fixed-point")))]
+compile_error!("box2d without `std` needs the `libm` feature for trig and square root");
+
+pub fn dot(a: Vec2, b: Vec2) -> Real {
     a.x * b.x + a.y * b.y
 }
 
-pub fn cross_v_v(a: Vec2, b: Vec2) -> f32 {
+pub fn cross_v_v(a: Vec2, b: Vec2) -> Real {
     a.x * b.y - a.y * b.x
 }
 
-pub fn cross_v_f(a: Vec2, s: f32) -> Vec2 {
+pub fn cross_v_f(a: Vec2, s: Real) -> Vec2 {
     Vec2::new(s * a.y, -s * a.x)
 }
 
-pub fn cross_f_v(s: f32, a: Vec2) -> Vec2 {
+pub fn cross_f_v(s: Real, a: Vec2) -> Vec2 {
     Vec2::new(-s * a.y, s * a.x)
 }
 
-impl std::ops::Neg for Vec2 {
+impl core::ops::Neg for Vec2 {
     type Output = Vec2;
     fn neg(self) -> Self::Output {
         Vec2::new(-self.x, -self.y)
     }
 }
 
-impl std::ops::Mul<f32> for Vec2 {
+impl core::ops::Mul<Real> for Vec2 {
     type Output = Vec2;
-    fn mul(self, s: f32) -> Self::Output {
+    fn mul(self, s: Real) -> Self::Output {
         Vec2::new(self.x * s, self.y * s)
     }
 }
 
-impl std::ops::Mul<Vec2> for f32 {
+impl core::ops::Mul<Vec2> for Real {
     type Output = Vec2;
     fn mul(self, v: Vec2) -> Self::Output {
         Vec2::new(self * v.x, self * v.y)
     }
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct Mat22 {
     pub col1: Vec2,
     pub col2: Vec2,
 }
 
 impl Mat22 {
-    pub fn new(col1: Vec2, col2: Vec2) -> Self {
+    pub const IDENTITY: Mat22 = Mat22::new(Vec2::X, Vec2::Y);
+
+    pub const fn new(col1: Vec2, col2: Vec2) -> Self {
         Self { col1, col2 }
     }
 
-    pub fn from_angle(angle: f32) -> Self {
-        let c = f32::cos(angle);
-        let s = f32::sin(angle);
+    pub fn from_angle(angle: Real) -> Self {
+        let c = cos(angle);
+        let s = sin(angle);
         Self {
             col1: Vec2::new(c, s),
             col2: Vec2::new(-s, c),
@@ -115,29 +361,56 @@ impl Mat22 {
         Self::new(self.col1.abs(), self.col2.abs())
     }
 
+    pub fn determinant(self) -> Real {
+        self.col1.x * self.col2.y - self.col2.x * self.col1.y
+    }
+
     pub fn invert(self) -> Self {
         let (a, b, c, d) = (self.col1.x, self.col2.x, self.col1.y, self.col2.y);
-        let det = 1.0 / (a * d - b * c);
+        let det = a * d - b * c;
+        // Singular when every degree of freedom involved is locked
+        let det = if det != 0.0 { 1.0 / det } else { real(0.0) };
 
         Mat22::new(Vec2::new(det * d, -det * c), Vec2::new(-det * b, det * a))
     }
 }
 
-impl std::ops::Mul for Mat22 {
+impl core::ops::Mul for Mat22 {
     type Output = Mat22;
     fn mul(self, other: Mat22) -> Self::Output {
         Mat22::new(self * other.col1, self * other.col2)
     }
 }
 
-impl std::ops::Add for Mat22 {
+impl core::ops::Add for Mat22 {
     type Output = Mat22;
     fn add(self, other: Mat22) -> Self::Output {
         Mat22::new(self.col1 + other.col1, self.col2 + other.col2)
     }
 }
 
-impl std::ops::Mul<Vec2> for Mat22 {
+impl core::ops::Sub for Mat22 {
+    type Output = Mat22;
+    fn sub(self, other: Mat22) -> Self::Output {
+        Mat22::new(self.col1 - other.col1, self.col2 - other.col2)
+    }
+}
+
+impl core::ops::Mul<Real> for Mat22 {
+    type Output = Mat22;
+    fn mul(self, s: Real) -> Self::Output {
+        Mat22::new(self.col1 * s, self.col2 * s)
+    }
+}
+
+impl core::ops::Neg for Mat22 {
+    type Output = Mat22;
+    fn neg(self) -> Self::Output {
+        Mat22::new(-self.col1, -self.col2)
+    }
+}
+
+impl core::ops::Mul<Vec2> for Mat22 {
     type Output = Vec2;
 
     fn mul(self, v: Vec2) -> Self::Output {
@@ -148,6 +421,534 @@ impl std::ops::Mul<Vec2> for Mat22 {
     }
 }
 
-pub fn clamp(a: f32, low: f32, high: f32) -> f32 {
-    f32::max(low, f32::min(a, high))
+// The same angle in (-pi, pi]
+pub fn unwind_angle(angle: Real) -> Real {
+    if angle > -PI && angle <= PI {
+        return angle;
+    }
+    let tau = real(2.0) * PI;
+    let angle = angle % tau;
+    if angle > PI {
+        angle - tau
+    } else if angle <= -PI {
+        angle + tau
+    } else {
+        angle
+    }
+}
+
+pub fn clamp(a: Real, low: Real, high: Real) -> Real {
+    Real::max(low, Real::min(a, high))
+}
+
+// Rotation kept as its cosine and sine so applying it needs no trig
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rot {
+    pub c: Real,
+    pub s: Real,
+}
+
+impl Default for Rot {
+    fn default() -> Self {
+        Rot::IDENTITY
+    }
+}
+
+impl Rot {
+    pub const IDENTITY: Rot = Rot {
+        c: real(1.0),
+        s: real(0.0),
+    };
+
+    pub fn from_angle(angle: Real) -> Self {
+        Rot {
+            c: cos(angle),
+            s: sin(angle),
+        }
+    }
+
+    // In (-pi, pi]
+    pub fn angle(self) -> Real {
+        atan2(self.s, self.c)
+    }
+
+    // Pulls the pair back onto the unit circle after accumulated round-off
+    pub fn normalize(self) -> Self {
+        let length = sqrt(self.c * self.c + self.s * self.s);
+        if length > 0.0 {
+            Rot {
+                c: self.c / length,
+                s: self.s / length,
+            }
+        } else {
+            Rot::IDENTITY
+        }
+    }
+
+    pub fn inverse(self) -> Self {
+        Rot {
+            c: self.c,
+            s: -self.s,
+        }
+    }
+
+    pub fn x_axis(self) -> Vec2 {
+        Vec2::new(self.c, self.s)
+    }
+
+    pub fn y_axis(self) -> Vec2 {
+        Vec2::new(-self.s, self.c)
+    }
+
+    pub fn rotate(self, v: Vec2) -> Vec2 {
+        Vec2::new(self.c * v.x - self.s * v.y, self.s * v.x + self.c * v.y)
+    }
+
+    pub fn inv_rotate(self, v: Vec2) -> Vec2 {
+        Vec2::new(self.c * v.x + self.s * v.y, -self.s * v.x + self.c * v.y)
+    }
+
+    // Normalized linear blend, close to slerp for the small angles between steps
+    pub fn nlerp(self, other: Rot, alpha: Real) -> Self {
+        Rot {
+            c: self.c + alpha * (other.c - self.c),
+            s: self.s + alpha * (other.s - self.s),
+        }
+        .normalize()
+    }
+}
+
+impl core::ops::Mul for Rot {
+    type Output = Rot;
+    fn mul(self, other: Rot) -> Self::Output {
+        Rot {
+            c: self.c * other.c - self.s * other.s,
+            s: self.s * other.c + self.c * other.s,
+        }
+    }
+}
+
+impl core::ops::Mul<Vec2> for Rot {
+    type Output = Vec2;
+    fn mul(self, v: Vec2) -> Self::Output {
+        self.rotate(v)
+    }
+}
+
+impl From<Rot> for Mat22 {
+    fn from(rot: Rot) -> Self {
+        Mat22::new(rot.x_axis(), rot.y_axis())
+    }
+}
+
+// Rigid transform, rotation then translation
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Transform {
+    pub position: Vec2,
+    pub rotation: Rot,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        position: Vec2::ZERO,
+        rotation: Rot::IDENTITY,
+    };
+
+    pub fn new(position: Vec2, rotation: Rot) -> Self {
+        Transform { position, rotation }
+    }
+
+    pub fn from_angle(position: Vec2, angle: Real) -> Self {
+        Transform::new(position, Rot::from_angle(angle))
+    }
+
+    // Local point to world
+    pub fn apply(self, v: Vec2) -> Vec2 {
+        self.rotation.rotate(v) + self.position
+    }
+
+    // World point to local
+    pub fn inv_apply(self, v: Vec2) -> Vec2 {
+        self.rotation.inv_rotate(v - self.position)
+    }
+
+    pub fn inverse(self) -> Self {
+        let rotation = self.rotation.inverse();
+        Transform::new(rotation.rotate(-self.position), rotation)
+    }
+
+    pub fn lerp(self, other: Transform, alpha: Real) -> Self {
+        Transform::new(
+            self.position.lerp(other.position, alpha),
+            self.rotation.nlerp(other.rotation, alpha),
+        )
+    }
+}
+
+impl core::ops::Mul for Transform {
+    type Output = Transform;
+    fn mul(self, other: Transform) -> Self::Output {
+        Transform::new(self.apply(other.position), self.rotation * other.rotation)
+    }
+}
+
+impl core::ops::Mul<Vec2> for Transform {
+    type Output = Vec2;
+    fn mul(self, v: Vec2) -> Self::Output {
+        self.apply(v)
+    }
+}
+
+// Axis-aligned bounding box
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Aabb {
+    pub lower: Vec2,
+    pub upper: Vec2,
+}
+
+impl Aabb {
+    pub fn new(lower: Vec2, upper: Vec2) -> Self {
+        Aabb { lower, upper }
+    }
+
+    // Bounds of a box with the given half extents placed by the transform
+    pub fn from_box(transform: Transform, half_extents: Vec2) -> Self {
+        let extents = Mat22::from(transform.rotation).abs() * half_extents;
+        Aabb::new(transform.position - extents, transform.position + extents)
+    }
+
+    pub fn center(self) -> Vec2 {
+        (self.lower + self.upper) * real(0.5)
+    }
+
+    pub fn half_extents(self) -> Vec2 {
+        (self.upper - self.lower) * real(0.5)
+    }
+
+    pub fn perimeter(self) -> Real {
+        let size = self.upper - self.lower;
+        real(2.0) * (size.x + size.y)
+    }
+
+    pub fn contains_point(self, point: Vec2) -> bool {
+        self.lower.x <= point.x
+            && point.x <= self.upper.x
+            && self.lower.y <= point.y
+            && point.y <= self.upper.y
+    }
+
+    pub fn overlaps(self, other: Aabb) -> bool {
+        self.lower.x <= other.upper.x
+            && other.lower.x <= self.upper.x
+            && self.lower.y <= other.upper.y
+            && other.lower.y <= self.upper.y
+    }
+
+    pub fn union(self, other: Aabb) -> Self {
+        Aabb::new(self.lower.min(other.lower), self.upper.max(other.upper))
+    }
+}
+
+#[cfg(feature = "mint")]
+mod mint_conversions {
+    use super::{Mat22, Real, Rot, Transform, Vec2};
+
+    impl From<mint::Vector2<Real>> for Vec2 {
+        fn from(v: mint::Vector2<Real>) -> Self {
+            Vec2::new(v.x, v.y)
+        }
+    }
+
+    impl From<Vec2> for mint::Vector2<Real> {
+        fn from(v: Vec2) -> Self {
+            mint::Vector2 { x: v.x, y: v.y }
+        }
+    }
+
+    impl From<mint::Point2<Real>> for Vec2 {
+        fn from(p: mint::Point2<Real>) -> Self {
+            Vec2::new(p.x, p.y)
+        }
+    }
+
+    impl From<Vec2> for mint::Point2<Real> {
+        fn from(v: Vec2) -> Self {
+            mint::Point2 { x: v.x, y: v.y }
+        }
+    }
+
+    impl From<mint::ColumnMatrix2<Real>> for Mat22 {
+        fn from(m: mint::ColumnMatrix2<Real>) -> Self {
+            Mat22::new(m.x.into(), m.y.into())
+        }
+    }
+
+    impl From<Mat22> for mint::ColumnMatrix2<Real> {
+        fn from(m: Mat22) -> Self {
+            mint::ColumnMatrix2 {
+                x: m.col1.into(),
+                y: m.col2.into(),
+            }
+        }
+    }
+
+    // Read from the first column, the matrix is taken to be a rotation
+    impl From<mint::ColumnMatrix2<Real>> for Rot {
+        fn from(m: mint::ColumnMatrix2<Real>) -> Self {
+            Rot { c: m.x.x, s: m.x.y }
+        }
+    }
+
+    impl From<Rot> for mint::ColumnMatrix2<Real> {
+        fn from(rot: Rot) -> Self {
+            Mat22::from(rot).into()
+        }
+    }
+
+    // Affine 2x3 matrix, the last column is the translation
+    impl From<mint::ColumnMatrix2x3<Real>> for Transform {
+        fn from(m: mint::ColumnMatrix2x3<Real>) -> Self {
+            Transform::new(m.z.into(), Rot { c: m.x.x, s: m.x.y })
+        }
+    }
+
+    impl From<Transform> for mint::ColumnMatrix2x3<Real> {
+        fn from(t: Transform) -> Self {
+            mint::ColumnMatrix2x3 {
+                x: t.rotation.x_axis().into(),
+                y: t.rotation.y_axis().into(),
+                z: t.position.into(),
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "glam", feature = "fixed-point"))]
+compile_error!("glam conversions need a float Real, not `fixed-point`");
+
+// glam::Vec2, Mat2 and Affine2 for f32, DVec2, DMat2 and DAffine2 with the
+// `f64` feature
+#[cfg(all(feature = "glam", not(feature = "fixed-point")))]
+mod glam_conversions {
+    use super::{Mat22, Rot, Transform, Vec2};
+    #[cfg(not(feature = "f64"))]
+    use glam::{Affine2 as GlamAffine2, Mat2 as GlamMat2, Vec2 as GlamVec2};
+    #[cfg(feature = "f64")]
+    use glam::{DAffine2 as GlamAffine2, DMat2 as GlamMat2, DVec2 as GlamVec2};
+
+    impl From<GlamVec2> for Vec2 {
+        fn from(v: GlamVec2) -> Self {
+            Vec2::new(v.x, v.y)
+        }
+    }
+
+    impl From<Vec2> for GlamVec2 {
+        fn from(v: Vec2) -> Self {
+            GlamVec2::new(v.x, v.y)
+        }
+    }
+
+    impl From<GlamMat2> for Mat22 {
+        fn from(m: GlamMat2) -> Self {
+            Mat22::new(m.x_axis.into(), m.y_axis.into())
+        }
+    }
+
+    impl From<Mat22> for GlamMat2 {
+        fn from(m: Mat22) -> Self {
+            GlamMat2::from_cols(m.col1.into(), m.col2.into())
+        }
+    }
+
+    // Read from the first column, the matrix is taken to be a rotation
+    impl From<GlamMat2> for Rot {
+        fn from(m: GlamMat2) -> Self {
+            Rot {
+                c: m.x_axis.x,
+                s: m.x_axis.y,
+            }
+        }
+    }
+
+    impl From<Rot> for GlamMat2 {
+        fn from(rot: Rot) -> Self {
+            Mat22::from(rot).into()
+        }
+    }
+
+    impl From<GlamAffine2> for Transform {
+        fn from(t: GlamAffine2) -> Self {
+            Transform::new(t.translation.into(), t.matrix2.into())
+        }
+    }
+
+    impl From<Transform> for GlamAffine2 {
+        fn from(t: Transform) -> Self {
+            GlamAffine2::from_mat2_translation(t.rotation.into(), t.position.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Components are small dyadic fractions, so every backend computes them exactly
+    fn v(x: f64, y: f64) -> Vec2 {
+        Vec2::new(real(x), real(y))
+    }
+
+    #[test]
+    fn vec2_arithmetic() {
+        let a = v(1.5, -2.0);
+        let b = v(0.25, 4.0);
+        assert_eq!(a + b, v(1.75, 2.0));
+        assert_eq!(a - b, v(1.25, -6.0));
+        assert_eq!(a * b, v(0.375, -8.0));
+        assert_eq!(-a, v(-1.5, 2.0));
+        assert_eq!(a * real(2.0), v(3.0, -4.0));
+        assert_eq!(real(2.0) * a, v(3.0, -4.0));
+        assert_eq!(a / real(4.0), v(0.375, -0.5));
+        assert_eq!(dot(a, b), real(-7.625));
+        assert_eq!(a.cross(b), real(6.5));
+        assert_eq!(cross_v_f(a, real(2.0)), v(-4.0, -3.0));
+        assert_eq!(cross_f_v(real(2.0), a), v(4.0, 3.0));
+        assert_eq!(a.perp(), v(2.0, 1.5));
+        assert_eq!(v(3.0, -4.0).length(), real(5.0));
+        assert_eq!(v(0.0, -4.0).normalize(), v(0.0, -1.0));
+        assert_eq!(Vec2::ZERO.normalize(), Vec2::ZERO);
+
+        let mut c = a;
+        c += b;
+        c -= v(0.75, 1.0);
+        c *= real(2.0);
+        c /= real(8.0);
+        assert_eq!(c, v(0.25, 0.25));
+    }
+
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn ref_ops_match_value_ops() {
+        let a = v(1.5, -2.0);
+        let b = v(0.25, 4.0);
+        let s = real(2.0);
+        assert_eq!(&a + b, a + b);
+        assert_eq!(a + &b, a + b);
+        assert_eq!(&a + &b, a + b);
+        assert_eq!(&a - b, a - b);
+        assert_eq!(a - &b, a - b);
+        assert_eq!(&a - &b, a - b);
+        assert_eq!(&a * b, a * b);
+        assert_eq!(a * &b, a * b);
+        assert_eq!(&a * &b, a * b);
+        assert_eq!(&a * s, a * s);
+        assert_eq!(a * &s, a * s);
+        assert_eq!(&a * &s, a * s);
+        assert_eq!(&a / s, a / s);
+        assert_eq!(a / &s, a / s);
+        assert_eq!(&a / &s, a / s);
+        assert_eq!(-&a, -a);
+    }
+
+    #[test]
+    fn vec2_sum() {
+        let vectors = [v(1.0, 2.0), v(-0.5, 0.25), v(3.0, -1.0)];
+        assert_eq!(vectors.iter().copied().sum::<Vec2>(), v(3.5, 1.25));
+        assert_eq!(core::iter::empty::<Vec2>().sum::<Vec2>(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn lerp_min_max() {
+        let a = v(1.0, -2.0);
+        let b = v(3.0, 2.0);
+        assert_eq!(a.lerp(b, real(0.0)), a);
+        assert_eq!(a.lerp(b, real(1.0)), b);
+        assert_eq!(a.lerp(b, real(0.25)), v(1.5, -1.0));
+        assert_eq!(v(1.0, 5.0).min(v(2.0, -1.0)), v(1.0, -1.0));
+        assert_eq!(v(1.0, 5.0).max(v(2.0, -1.0)), v(2.0, 5.0));
+        assert_eq!(clamp(real(3.0), real(-1.0), real(2.0)), real(2.0));
+        assert_eq!(clamp(real(-3.0), real(-1.0), real(2.0)), real(-1.0));
+    }
+
+    #[test]
+    fn mat22_invert() {
+        let m = Mat22::new(v(2.0, 1.0), v(1.0, 1.0));
+        assert_eq!(m.determinant(), real(1.0));
+        assert_eq!(m.invert() * m, Mat22::IDENTITY);
+        assert_eq!(m * m.invert(), Mat22::IDENTITY);
+
+        // Singular, e.g. every degree of freedom locked: zero, not NaN
+        let singular = Mat22::new(v(1.0, 2.0), v(2.0, 4.0));
+        assert_eq!(singular.invert(), Mat22::default());
+        assert_eq!(Mat22::default().invert(), Mat22::default());
+    }
+
+    #[test]
+    fn array_and_tuple_conversions() {
+        let a = v(1.5, -2.0);
+        assert_eq!(<[Real; 2]>::from(a), [real(1.5), real(-2.0)]);
+        assert_eq!(<(Real, Real)>::from(a), (real(1.5), real(-2.0)));
+        assert_eq!(Vec2::from([real(1.5), real(-2.0)]), a);
+        assert_eq!(Vec2::from((real(1.5), real(-2.0))), a);
+    }
+
+    #[test]
+    #[cfg(feature = "mint")]
+    fn mint_conversions() {
+        let a = v(1.5, -2.0);
+        let vector: mint::Vector2<Real> = a.into();
+        assert_eq!((vector.x, vector.y), (a.x, a.y));
+        assert_eq!(Vec2::from(vector), a);
+        let point: mint::Point2<Real> = a.into();
+        assert_eq!(Vec2::from(point), a);
+
+        let m = Mat22::new(v(2.0, 1.0), v(-1.0, 3.0));
+        let matrix: mint::ColumnMatrix2<Real> = m.into();
+        assert_eq!((matrix.y.x, matrix.y.y), (real(-1.0), real(3.0)));
+        assert_eq!(Mat22::from(matrix), m);
+
+        let rot = Rot {
+            c: real(0.6),
+            s: real(0.8),
+        };
+        let matrix: mint::ColumnMatrix2<Real> = rot.into();
+        assert_eq!(Mat22::from(matrix), Mat22::from(rot));
+        assert_eq!(Rot::from(matrix), rot);
+
+        let transform = Transform::new(a, rot);
+        let affine: mint::ColumnMatrix2x3<Real> = transform.into();
+        assert_eq!(Vec2::from(affine.z), a);
+        assert_eq!(Transform::from(affine), transform);
+    }
+
+    #[test]
+    #[cfg(feature = "glam")]
+    fn glam_conversions() {
+        #[cfg(not(feature = "f64"))]
+        use glam::{Affine2 as GlamAffine2, Mat2 as GlamMat2, Vec2 as GlamVec2};
+        #[cfg(feature = "f64")]
+        use glam::{DAffine2 as GlamAffine2, DMat2 as GlamMat2, DVec2 as GlamVec2};
+
+        let a = v(1.5, -2.0);
+        let vector: GlamVec2 = a.into();
+        assert_eq!(vector, GlamVec2::new(a.x, a.y));
+        assert_eq!(Vec2::from(vector), a);
+
+        let m = Mat22::new(v(2.0, 1.0), v(-1.0, 3.0));
+        let matrix: GlamMat2 = m.into();
+        assert_eq!(matrix * vector, (m * a).into());
+        assert_eq!(Mat22::from(matrix), m);
+
+        let rot = Rot {
+            c: real(0.6),
+            s: real(0.8),
+        };
+        assert_eq!(Rot::from(GlamMat2::from(rot)), rot);
+
+        let transform = Transform::new(a, rot);
+        let affine: GlamAffine2 = transform.into();
+        let p = v(0.5, 0.25);
+        assert_eq!(Vec2::from(affine.transform_point2(p.into())), transform * p);
+        assert_eq!(Transform::from(affine), transform);
+    }
 }
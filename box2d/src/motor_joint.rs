@@ -0,0 +1,192 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, StepContext};
+use crate::joint::mass_matrix;
+use crate::math_utils::{Mat22, Real, Vec2, clamp, real, unwind_angle};
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Drives the position and angle of body2 relative to body1 towards a target
+// offset with limited force and torque. `linear_offset` is in body1 frame.
+pub struct MotorJoint {
+    m: Mat22,
+    r1: Vec2, // target point relative to body1, body2 is driven by its center
+    angular_mass: Real,
+    linear_error: Vec2,
+    angular_error: Real,
+    dt: Real,
+    inv_dt: Real,
+    p: Vec2,               // accumulated linear impulse
+    angular_impulse: Real, // accumulated angular impulse
+    ids: (usize, usize),
+    body1: Rc<RefCell<Body>>,
+    body2: Rc<RefCell<Body>>,
+    pub linear_offset: Vec2,
+    pub angular_offset: Real,
+    pub max_force: Real,
+    pub max_torque: Real,
+    pub correction_factor: Real,
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
+}
+
+impl MotorJoint {
+    // The target offset starts at the current relative pose of the bodies.
+    // There is no sensible default strength, the force and torque limits
+    // scale with the masses involved.
+    pub fn new(
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        max_force: Real,
+        max_torque: Real,
+    ) -> Self {
+        let (linear_offset, angular_offset) = {
+            let body1 = &body1.borrow();
+            let body2 = &body2.borrow();
+            let rot1t = Mat22::from(body1.rot).transpose();
+            (
+                rot1t * (body2.position - body1.position),
+                unwind_angle(body2.rotation - body1.rotation),
+            )
+        };
+
+        Self {
+            m: Mat22::default(),
+            r1: Vec2::default(),
+            angular_mass: real(0.0),
+            linear_error: Vec2::default(),
+            angular_error: real(0.0),
+            dt: real(0.0),
+            inv_dt: real(0.0),
+            p: Vec2::default(),
+            angular_impulse: real(0.0),
+            ids: (0, 0),
+            body1,
+            body2,
+            linear_offset,
+            angular_offset,
+            max_force,
+            max_torque,
+            correction_factor: real(0.3),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
+        }
+    }
+}
+
+impl Constraint for MotorJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        let inv_dt = step.inv_dt;
+        self.inv_dt = inv_dt;
+        self.dt = if inv_dt > 0.0 {
+            1.0 / inv_dt
+        } else {
+            real(0.0)
+        };
+        self.ids = (step.bodies.id(&self.body1), step.bodies.id(&self.body2));
+        let (id1, id2) = self.ids;
+        let bodies = &mut *step.bodies;
+
+        // The linear row pulls the center of body2 to the target point,
+        // which is offset from the center of body1
+        self.r1 = Mat22::from(bodies.rot(id1)) * self.linear_offset;
+        self.m = mass_matrix(bodies, self.ids, self.r1, Vec2::ZERO).invert();
+
+        let inv_i = bodies.angular_inv_i(id1) + bodies.angular_inv_i(id2);
+        self.angular_mass = if inv_i > 0.0 { 1.0 / inv_i } else { real(0.0) };
+
+        self.linear_error = bodies.position(id2) - bodies.position(id1) - self.r1;
+        self.angular_error =
+            unwind_angle(bodies.rotation(id2) - bodies.rotation(id1) - self.angular_offset);
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            bodies.sub_velocity(id1, self.r1, self.p);
+            bodies.apply_angular_impulse(id1, -self.angular_impulse);
+            bodies.add_velocity(id2, Vec2::default(), self.p);
+            bodies.apply_angular_impulse(id2, self.angular_impulse);
+        } else {
+            self.p.set(real(0.0), real(0.0));
+            self.angular_impulse = real(0.0);
+        }
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let (id1, id2) = self.ids;
+        // Relax iterations only remove the velocity the correction added
+        let correction = if step.use_bias {
+            self.inv_dt * self.correction_factor
+        } else {
+            real(0.0)
+        };
+        let bodies = &mut *step.bodies;
+
+        // Angular constraint
+        {
+            let w = bodies.angular_velocity(id2) - bodies.angular_velocity(id1);
+            let impulse = -self.angular_mass * (w + correction * self.angular_error);
+
+            // Clamp the accumulated impulse
+            let max_impulse = self.max_torque * self.dt;
+            let old_impulse = self.angular_impulse;
+            self.angular_impulse = clamp(old_impulse + impulse, -max_impulse, max_impulse);
+            let impulse = self.angular_impulse - old_impulse;
+            bodies.apply_angular_impulse(id1, -impulse);
+            bodies.apply_angular_impulse(id2, impulse);
+        }
+
+        // Linear constraint
+        {
+            let dv = bodies.velocity(id2) - bodies.velocity_at(id1, self.r1);
+            let impulse = self.m * -(dv + correction * self.linear_error);
+
+            // Clamp the accumulated impulse
+            let old_p = self.p;
+            self.p += impulse;
+            let max_impulse = self.max_force * self.dt;
+            let length = self.p.length();
+            if length > max_impulse {
+                self.p = (max_impulse / length) * self.p;
+            }
+            let impulse = self.p - old_p;
+            bodies.sub_velocity(id1, self.r1, impulse);
+            bodies.add_velocity(id2, Vec2::default(), impulse);
+        }
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        inv_dt * self.p
+    }
+
+    fn reaction_torque(&self, inv_dt: Real) -> Real {
+        inv_dt * self.angular_impulse
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![self.body1.clone(), self.body2.clone()]
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        let b1 = self.body1.borrow();
+        let b2 = self.body2.borrow();
+        let target = b1.transform() * self.linear_offset;
+
+        vec![(b1.position, target), (target, b2.position)]
+    }
+}
@@ -0,0 +1,169 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, StepContext};
+use crate::math_utils::{Mat22, PI, Real, Vec2, real};
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Pulls an anchor point of a single body towards a moving world target with
+// a damped spring of limited force. Used to drag bodies around.
+pub struct MouseJoint {
+    local_anchor: Vec2,
+    target: Vec2,
+    r: Vec2,
+    m: Mat22,
+    bias: Vec2,
+    gamma: Real,
+    dt: Real,
+    p: Vec2, // accumulated impulse
+    id: usize,
+    body: Rc<RefCell<Body>>,
+    pub max_force: Real,
+    pub frequency_hz: Real,
+    pub damping_ratio: Real,
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+}
+
+impl MouseJoint {
+    pub fn new(body: Rc<RefCell<Body>>, target: Vec2) -> Self {
+        let local_anchor = {
+            let body = body.borrow();
+            Mat22::from(body.rot).transpose() * (target - body.position)
+        };
+
+        Self {
+            local_anchor,
+            target,
+            r: Vec2::default(),
+            m: Mat22::default(),
+            bias: Vec2::default(),
+            gamma: real(0.0),
+            dt: real(0.0),
+            p: Vec2::default(),
+            id: 0,
+            body,
+            max_force: real(0.0),
+            frequency_hz: real(5.0),
+            damping_ratio: real(0.7),
+            break_force: None,
+            break_torque: None,
+        }
+    }
+
+    pub fn target(&self) -> Vec2 {
+        self.target
+    }
+
+    pub fn set_target(&mut self, target: Vec2) {
+        self.target = target;
+    }
+
+    pub fn body(&self) -> Rc<RefCell<Body>> {
+        self.body.clone()
+    }
+}
+
+impl Constraint for MouseJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        let inv_dt = step.inv_dt;
+        self.dt = if inv_dt > 0.0 {
+            1.0 / inv_dt
+        } else {
+            real(0.0)
+        };
+        self.id = step.bodies.id(&self.body);
+        let id = self.id;
+        let bodies = &mut *step.bodies;
+        let mass = bodies.mass(id);
+
+        // frequency in radians
+        let omega = 2.0 * PI * self.frequency_hz;
+        // damping coefficient
+        let d = 2.0 * mass * self.damping_ratio * omega;
+        // spring stiffness
+        let k = mass * omega * omega;
+
+        let gamma = self.dt * (d + self.dt * k);
+        self.gamma = if gamma > 0.0 { 1.0 / gamma } else { real(0.0) };
+        let beta = self.dt * k * self.gamma;
+
+        self.r = Mat22::from(bodies.rot(id)) * self.local_anchor;
+
+        // Same mass matrix as Joint with a static second body, softened by gamma
+        let inv_mass = bodies.linear_inv_mass(id);
+        let inv_i = bodies.angular_inv_i(id);
+        let mut k = Mat22::default();
+        (k.col1.x, k.col2.x) = (
+            inv_mass.x + inv_i * self.r.y * self.r.y + self.gamma,
+            -inv_i * self.r.x * self.r.y,
+        );
+        (k.col1.y, k.col2.y) = (
+            -inv_i * self.r.x * self.r.y,
+            inv_mass.y + inv_i * self.r.x * self.r.x + self.gamma,
+        );
+        self.m = k.invert();
+
+        self.bias = beta * ((bodies.position(id) + self.r) - self.target);
+
+        // Cheap damping of the spin of a dragged body, once per step
+        if step.substep == 0 {
+            let angular_velocity = bodies.angular_velocity(id) * real(0.98);
+            bodies.set_angular_velocity(id, angular_velocity);
+        }
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            bodies.add_velocity(id, self.r, self.p);
+        } else {
+            self.p.set(real(0.0), real(0.0));
+        }
+    }
+
+    // Only a spring, so the relax iteration solves it the same
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let dv = step.bodies.velocity_at(self.id, self.r);
+        let impulse = self.m * -(dv + self.bias + self.gamma * self.p);
+
+        // Clamp the accumulated impulse
+        let old_p = self.p;
+        self.p += impulse;
+        let max_impulse = self.max_force * self.dt;
+        let length = self.p.length();
+        if length > max_impulse {
+            self.p = (max_impulse / length) * self.p;
+        }
+
+        step.bodies.add_velocity(self.id, self.r, self.p - old_p);
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        inv_dt * self.p
+    }
+
+    fn reaction_torque(&self, _inv_dt: Real) -> Real {
+        real(0.0)
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![self.body.clone()]
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        let body = self.body.borrow();
+        let x = body.position;
+        let p = body.transform() * self.local_anchor;
+
+        vec![(x, p), (p, self.target)]
+    }
+}
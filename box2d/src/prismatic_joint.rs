@@ -0,0 +1,319 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, LimitBias, Softness, StepContext};
+use crate::math_utils::{Mat22, Real, Vec2, clamp, cross_f_v, cross_v_v, dot, real, unwind_angle};
+use crate::solver_bodies::SolverBodies;
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Restricts body2 to slide along an axis fixed in body1, relative rotation is
+// locked. Optional lower/upper translation limits and a linear motor.
+pub struct PrismaticJoint {
+    pub(crate) local_anchor1: Vec2,
+    pub(crate) local_anchor2: Vec2,
+    pub(crate) local_axis: Vec2, // unit slide axis in body1 frame
+    reference_angle: Real,
+    r1: Vec2, // from body1 center to anchor2, impulses act on body1 there
+    r2: Vec2,
+    axis: Vec2,
+    perp: Vec2,
+    m: Mat22, // inverted mass matrix of the perpendicular and angular rows
+    axial_mass: Real,
+    dt: Real,
+    bias: Vec2,
+    soft: Softness, // of the perpendicular and angular rows
+    lower_bias: LimitBias,
+    upper_bias: LimitBias,
+    p: Vec2,             // accumulated perpendicular and angular impulse
+    motor_impulse: Real, // accumulated motor impulse
+    lower_impulse: Real, // accumulated lower limit impulse
+    upper_impulse: Real, // accumulated upper limit impulse
+    ids: (usize, usize),
+    pub(crate) body1: Rc<RefCell<Body>>,
+    pub(crate) body2: Rc<RefCell<Body>>,
+    pub enable_limit: bool,
+    pub lower_translation: Real,
+    pub upper_translation: Real,
+    pub enable_motor: bool,
+    pub motor_speed: Real,
+    pub max_motor_force: Real,
+    pub bias_factor: Real,
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
+}
+
+impl PrismaticJoint {
+    // `axis` is normalized, panics if it is zero
+    pub fn new(
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor: Vec2,
+        axis: Vec2,
+    ) -> Self {
+        assert!(axis.length_squared() > 0.0, "joint axis must be non-zero");
+        let (local_anchor1, local_anchor2, local_axis, reference_angle) = {
+            let body1 = &body1.borrow();
+            let body2 = &body2.borrow();
+
+            let rot1t = Mat22::from(body1.rot).transpose();
+            let rot2t = Mat22::from(body2.rot).transpose();
+
+            (
+                rot1t * (anchor - body1.position),
+                rot2t * (anchor - body2.position),
+                rot1t * axis.normalize(),
+                unwind_angle(body2.rotation - body1.rotation),
+            )
+        };
+
+        Self {
+            local_anchor1,
+            local_anchor2,
+            local_axis,
+            reference_angle,
+            r1: Vec2::default(),
+            r2: Vec2::default(),
+            axis: Vec2::default(),
+            perp: Vec2::default(),
+            m: Mat22::default(),
+            axial_mass: real(0.0),
+            dt: real(0.0),
+            bias: Vec2::default(),
+            soft: Softness::RIGID,
+            lower_bias: LimitBias::default(),
+            upper_bias: LimitBias::default(),
+            p: Vec2::default(),
+            motor_impulse: real(0.0),
+            lower_impulse: real(0.0),
+            upper_impulse: real(0.0),
+            ids: (0, 0),
+            body1,
+            body2,
+            enable_limit: false,
+            lower_translation: real(0.0),
+            upper_translation: real(0.0),
+            enable_motor: false,
+            motor_speed: real(0.0),
+            max_motor_force: real(0.0),
+            bias_factor: real(0.2),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
+        }
+    }
+
+    // Offset of anchor2 from anchor1 along the slide axis
+    pub fn translation(&self) -> Real {
+        let body1 = self.body1.borrow();
+        let body2 = self.body2.borrow();
+        let rot1 = Mat22::from(body1.rot);
+        let p1 = body1.position + rot1 * self.local_anchor1;
+        let p2 = body2.transform() * self.local_anchor2;
+        dot(rot1 * self.local_axis, p2 - p1)
+    }
+
+    pub fn set_limits(&mut self, lower_translation: Real, upper_translation: Real) {
+        self.lower_translation = Real::min(lower_translation, upper_translation);
+        self.upper_translation = Real::max(lower_translation, upper_translation);
+    }
+
+    // Relative velocity of anchor2 and body1 material at anchor2 along `dir`
+    fn relative_velocity(&self, bodies: &SolverBodies, dir: Vec2) -> Real {
+        let (id1, id2) = self.ids;
+        let dv = bodies.velocity_at(id2, self.r2) - bodies.velocity_at(id1, self.r1);
+        dot(dir, dv)
+    }
+
+    fn relative_angular_velocity(&self, bodies: &SolverBodies) -> Real {
+        let (id1, id2) = self.ids;
+        bodies.angular_velocity(id2) - bodies.angular_velocity(id1)
+    }
+
+    // Apply a linear impulse `p` at the anchors and an extra angular impulse
+    fn apply(&self, bodies: &mut SolverBodies, p: Vec2, angular: Real) {
+        let (id1, id2) = self.ids;
+        bodies.sub_velocity(id1, self.r1, p);
+        bodies.apply_angular_impulse(id1, -angular);
+        bodies.add_velocity(id2, self.r2, p);
+        bodies.apply_angular_impulse(id2, angular);
+    }
+}
+
+impl Constraint for PrismaticJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        let inv_dt = step.inv_dt;
+        self.dt = if inv_dt > 0.0 {
+            1.0 / inv_dt
+        } else {
+            real(0.0)
+        };
+        self.ids = (step.bodies.id(&self.body1), step.bodies.id(&self.body2));
+        let (id1, id2) = self.ids;
+        let translation = {
+            let bodies = &*step.bodies;
+            let rot1 = Mat22::from(bodies.rot(id1));
+            let r1 = rot1 * self.local_anchor1;
+            self.r2 = Mat22::from(bodies.rot(id2)) * self.local_anchor2;
+            let d = (bodies.position(id2) + self.r2) - (bodies.position(id1) + r1);
+            self.r1 = d + r1;
+
+            self.axis = rot1 * self.local_axis;
+            self.perp = cross_f_v(real(1.0), self.axis);
+
+            let inv_i1 = bodies.angular_inv_i(id1);
+            let inv_i2 = bodies.angular_inv_i(id2);
+
+            // Motor and limits act along the axis
+            let a1 = cross_v_v(self.r1, self.axis);
+            let a2 = cross_v_v(self.r2, self.axis);
+            let k_axial = bodies.inv_mass_along(id1, self.axis)
+                + bodies.inv_mass_along(id2, self.axis)
+                + inv_i1 * a1 * a1
+                + inv_i2 * a2 * a2;
+            self.axial_mass = if k_axial > 0.0 {
+                1.0 / k_axial
+            } else {
+                real(0.0)
+            };
+
+            // Perpendicular and angular rows are solved together
+            let s1 = cross_v_v(self.r1, self.perp);
+            let s2 = cross_v_v(self.r2, self.perp);
+            let k11 = bodies.inv_mass_along(id1, self.perp)
+                + bodies.inv_mass_along(id2, self.perp)
+                + inv_i1 * s1 * s1
+                + inv_i2 * s2 * s2;
+            let k12 = inv_i1 * s1 + inv_i2 * s2;
+            let k22 = inv_i1 + inv_i2;
+            // Both rotations fixed: keep the angular row solvable
+            let k22 = if k22 > 0.0 { k22 } else { real(1.0) };
+            self.m = Mat22::new(Vec2::new(k11, k12), Vec2::new(k12, k22)).invert();
+
+            self.soft = step.rigid_softness(self.bias_factor);
+            let angle =
+                unwind_angle(bodies.rotation(id2) - bodies.rotation(id1) - self.reference_angle);
+            self.bias = -self.soft.bias_rate * Vec2::new(dot(self.perp, d), angle);
+
+            dot(self.axis, d)
+        };
+
+        if self.enable_limit {
+            self.lower_bias =
+                LimitBias::new(translation - self.lower_translation, self.bias_factor, step);
+            self.upper_bias =
+                LimitBias::new(self.upper_translation - translation, self.bias_factor, step);
+        } else {
+            self.lower_impulse = real(0.0);
+            self.upper_impulse = real(0.0);
+        }
+        if !self.enable_motor {
+            self.motor_impulse = real(0.0);
+        }
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            let axial = self.motor_impulse + self.lower_impulse - self.upper_impulse;
+            let p = (self.p.x * self.perp) + (axial * self.axis);
+            self.apply(step.bodies, p, self.p.y);
+        } else {
+            self.p.set(real(0.0), real(0.0));
+            self.motor_impulse = real(0.0);
+            self.lower_impulse = real(0.0);
+            self.upper_impulse = real(0.0);
+        }
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let use_bias = step.use_bias;
+        if self.enable_motor {
+            let vn = self.relative_velocity(step.bodies, self.axis);
+            let impulse = self.axial_mass * (self.motor_speed - vn);
+
+            // Clamp the accumulated impulse
+            let max_impulse = self.max_motor_force * self.dt;
+            let old_impulse = self.motor_impulse;
+            self.motor_impulse = clamp(old_impulse + impulse, -max_impulse, max_impulse);
+            let impulse = self.motor_impulse - old_impulse;
+            self.apply(step.bodies, impulse * self.axis, real(0.0));
+        }
+
+        if self.enable_limit {
+            // Lower limit
+            {
+                let vn = self.relative_velocity(step.bodies, self.axis);
+                let impulse =
+                    self.lower_bias
+                        .impulse(self.axial_mass, vn, self.lower_impulse, use_bias);
+                let old_impulse = self.lower_impulse;
+                self.lower_impulse = Real::max(real(0.0), old_impulse + impulse);
+                let impulse = self.lower_impulse - old_impulse;
+                self.apply(step.bodies, impulse * self.axis, real(0.0));
+            }
+
+            // Upper limit
+            {
+                let vn = -self.relative_velocity(step.bodies, self.axis);
+                let impulse =
+                    self.upper_bias
+                        .impulse(self.axial_mass, vn, self.upper_impulse, use_bias);
+                let old_impulse = self.upper_impulse;
+                self.upper_impulse = Real::max(real(0.0), old_impulse + impulse);
+                let impulse = self.upper_impulse - old_impulse;
+                self.apply(step.bodies, -impulse * self.axis, real(0.0));
+            }
+        }
+
+        // Perpendicular and angular constraint
+        let dv = Vec2::new(
+            self.relative_velocity(step.bodies, self.perp),
+            self.relative_angular_velocity(step.bodies),
+        );
+        let impulse = if use_bias {
+            let impulse = self.m * (self.bias - dv);
+            self.soft.mass_scale * impulse - self.soft.impulse_scale * self.p
+        } else {
+            self.m * -dv
+        };
+        self.apply(step.bodies, impulse.x * self.perp, impulse.y);
+        self.p += impulse;
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        let axial = self.motor_impulse + self.lower_impulse - self.upper_impulse;
+        inv_dt * ((self.p.x * self.perp) + (axial * self.axis))
+    }
+
+    fn reaction_torque(&self, inv_dt: Real) -> Real {
+        inv_dt * self.p.y
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![self.body1.clone(), self.body2.clone()]
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        let b1 = self.body1.borrow();
+        let b2 = self.body2.borrow();
+        let x1 = b1.position;
+        let p1 = b1.transform() * self.local_anchor1;
+        let x2 = b2.position;
+        let p2 = b2.transform() * self.local_anchor2;
+
+        vec![(x1, p1), (p1, p2), (x2, p2)]
+    }
+}
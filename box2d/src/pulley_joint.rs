@@ -0,0 +1,201 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, Softness, StepContext};
+use crate::math_utils::{Mat22, Real, Vec2, cross_v_v, dot, real};
+use crate::solver_bodies::SolverBodies;
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Two bodies hanging from fixed ground anchors on one rope:
+// length1 + ratio * length2 stays constant.
+pub struct PulleyJoint {
+    ground_anchor1: Vec2,
+    ground_anchor2: Vec2,
+    local_anchor1: Vec2,
+    local_anchor2: Vec2,
+    constant: Real,
+    r1: Vec2,
+    r2: Vec2,
+    u1: Vec2, // unit rope direction from ground anchor1 to anchor1
+    u2: Vec2, // unit rope direction from ground anchor2 to anchor2
+    mass: Real,
+    bias: Real,
+    soft: Softness,
+    impulse: Real, // accumulated impulse
+    ids: (usize, usize),
+    body1: Rc<RefCell<Body>>,
+    body2: Rc<RefCell<Body>>,
+    pub ratio: Real,
+    pub bias_factor: Real,
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
+}
+
+impl PulleyJoint {
+    pub fn new(
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        ground_anchors: [Vec2; 2],
+        anchors: [Vec2; 2],
+        ratio: Real,
+    ) -> Self {
+        let [ground_anchor1, ground_anchor2] = ground_anchors;
+        let [anchor1, anchor2] = anchors;
+        let (local_anchor1, local_anchor2) = {
+            let body1 = &body1.borrow();
+            let body2 = &body2.borrow();
+
+            let rot1t = Mat22::from(body1.rot).transpose();
+            let rot2t = Mat22::from(body2.rot).transpose();
+
+            (
+                rot1t * (anchor1 - body1.position),
+                rot2t * (anchor2 - body2.position),
+            )
+        };
+        let length1 = (anchor1 - ground_anchor1).length();
+        let length2 = (anchor2 - ground_anchor2).length();
+
+        Self {
+            ground_anchor1,
+            ground_anchor2,
+            local_anchor1,
+            local_anchor2,
+            constant: length1 + ratio * length2,
+            r1: Vec2::default(),
+            r2: Vec2::default(),
+            u1: Vec2::default(),
+            u2: Vec2::default(),
+            mass: real(0.0),
+            bias: real(0.0),
+            soft: Softness::RIGID,
+            impulse: real(0.0),
+            ids: (0, 0),
+            body1,
+            body2,
+            ratio,
+            bias_factor: real(0.2),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
+        }
+    }
+
+    pub fn length1(&self) -> Real {
+        let body1 = self.body1.borrow();
+        let p1 = body1.transform() * self.local_anchor1;
+        (p1 - self.ground_anchor1).length()
+    }
+
+    pub fn length2(&self) -> Real {
+        let body2 = self.body2.borrow();
+        let p2 = body2.transform() * self.local_anchor2;
+        (p2 - self.ground_anchor2).length()
+    }
+
+    fn apply(&self, bodies: &mut SolverBodies, impulse: Real) {
+        let (id1, id2) = self.ids;
+        bodies.add_velocity(id1, self.r1, -impulse * self.u1);
+        bodies.add_velocity(id2, self.r2, (-self.ratio * impulse) * self.u2);
+    }
+}
+
+impl Constraint for PulleyJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        self.ids = (step.bodies.id(&self.body1), step.bodies.id(&self.body2));
+        let (id1, id2) = self.ids;
+        {
+            let bodies = &*step.bodies;
+            self.r1 = Mat22::from(bodies.rot(id1)) * self.local_anchor1;
+            self.r2 = Mat22::from(bodies.rot(id2)) * self.local_anchor2;
+
+            let d1 = bodies.position(id1) + self.r1 - self.ground_anchor1;
+            let d2 = bodies.position(id2) + self.r2 - self.ground_anchor2;
+            let length1 = d1.length();
+            let length2 = d2.length();
+            self.u1 = if length1 > Real::EPSILON {
+                (1.0 / length1) * d1
+            } else {
+                Vec2::default()
+            };
+            self.u2 = if length2 > Real::EPSILON {
+                (1.0 / length2) * d2
+            } else {
+                Vec2::default()
+            };
+
+            let ru1 = cross_v_v(self.r1, self.u1);
+            let ru2 = cross_v_v(self.r2, self.u2);
+            let k1 = bodies.inv_mass_along(id1, self.u1) + bodies.angular_inv_i(id1) * ru1 * ru1;
+            let k2 = bodies.inv_mass_along(id2, self.u2) + bodies.angular_inv_i(id2) * ru2 * ru2;
+            let k = k1 + self.ratio * self.ratio * k2;
+            self.mass = if k > 0.0 { 1.0 / k } else { real(0.0) };
+
+            self.soft = step.rigid_softness(self.bias_factor);
+            let c = self.constant - length1 - self.ratio * length2;
+            self.bias = self.soft.bias_rate * c;
+        }
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            self.apply(step.bodies, self.impulse);
+        } else {
+            self.impulse = real(0.0);
+        }
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let (id1, id2) = self.ids;
+        let v1 = step.bodies.velocity_at(id1, self.r1);
+        let v2 = step.bodies.velocity_at(id2, self.r2);
+        let vn = -dot(self.u1, v1) - self.ratio * dot(self.u2, v2);
+
+        let impulse = if step.use_bias {
+            self.soft.impulse(self.mass, vn, self.bias, self.impulse)
+        } else {
+            -self.mass * vn
+        };
+        self.impulse += impulse;
+        self.apply(step.bodies, impulse);
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        (-inv_dt * self.ratio * self.impulse) * self.u2
+    }
+
+    fn reaction_torque(&self, _inv_dt: Real) -> Real {
+        real(0.0)
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![self.body1.clone(), self.body2.clone()]
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        let b1 = self.body1.borrow();
+        let b2 = self.body2.borrow();
+        let p1 = b1.transform() * self.local_anchor1;
+        let p2 = b2.transform() * self.local_anchor2;
+
+        vec![
+            (p1, self.ground_anchor1),
+            (self.ground_anchor1, self.ground_anchor2),
+            (self.ground_anchor2, p2),
+        ]
+    }
+}
@@ -0,0 +1,234 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, LimitBias, StepContext};
+use crate::joint::Joint;
+use crate::math_utils::{Real, Vec2, clamp, real, unwind_angle};
+use crate::solver_bodies::SolverBodies;
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Point joint with control over the relative angle of the two bodies:
+// optional lower/upper angle limits and a motor driving the relative
+// angular velocity towards `motor_speed`.
+pub struct RevoluteJoint {
+    pub(crate) point: Joint,
+    pub(crate) reference_angle: Real,
+    // Joint angle followed through full turns, body angles wrap at pi
+    angle: Real,
+    axial_mass: Real,
+    dt: Real,
+    lower_bias: LimitBias,
+    upper_bias: LimitBias,
+    motor_impulse: Real, // accumulated motor impulse
+    lower_impulse: Real, // accumulated lower limit impulse
+    upper_impulse: Real, // accumulated upper limit impulse
+    pub enable_limit: bool,
+    pub lower_angle: Real,
+    pub upper_angle: Real,
+    pub enable_motor: bool,
+    pub motor_speed: Real,
+    pub max_motor_torque: Real,
+    pub bias_factor: Real,
+    pub softness: Real, // of the anchor, 0 means rigid
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
+}
+
+impl RevoluteJoint {
+    pub fn new(body1: Rc<RefCell<Body>>, body2: Rc<RefCell<Body>>, anchor: Vec2) -> Self {
+        let reference_angle = unwind_angle(body2.borrow().rotation - body1.borrow().rotation);
+        Self {
+            point: Joint::new(body1, body2, anchor),
+            reference_angle,
+            angle: real(0.0),
+            axial_mass: real(0.0),
+            dt: real(0.0),
+            lower_bias: LimitBias::default(),
+            upper_bias: LimitBias::default(),
+            motor_impulse: real(0.0),
+            lower_impulse: real(0.0),
+            upper_impulse: real(0.0),
+            enable_limit: false,
+            lower_angle: real(0.0),
+            upper_angle: real(0.0),
+            enable_motor: false,
+            motor_speed: real(0.0),
+            max_motor_torque: real(0.0),
+            bias_factor: real(0.2),
+            softness: real(0.0),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
+        }
+    }
+
+    pub fn body1(&self) -> Rc<RefCell<Body>> {
+        self.point.body1.clone()
+    }
+
+    pub fn body2(&self) -> Rc<RefCell<Body>> {
+        self.point.body2.clone()
+    }
+
+    // Anchor in body1 coordinates
+    pub fn local_anchor1(&self) -> Vec2 {
+        self.point.local_anchor1
+    }
+
+    // Anchor in body2 coordinates
+    pub fn local_anchor2(&self) -> Vec2 {
+        self.point.local_anchor2
+    }
+
+    // Rotation of body2 relative to body1 since the joint was created,
+    // counting full turns as long as it turns less than pi per step
+    pub fn angle(&self) -> Real {
+        let rotation1 = self.point.body1.borrow().rotation;
+        let rotation2 = self.point.body2.borrow().rotation;
+        self.follow_angle(rotation1, rotation2)
+    }
+
+    fn follow_angle(&self, rotation1: Real, rotation2: Real) -> Real {
+        let angle = rotation2 - rotation1 - self.reference_angle;
+        self.angle + unwind_angle(angle - self.angle)
+    }
+
+    // The limits may lie more than half a turn apart
+    pub fn set_limits(&mut self, lower_angle: Real, upper_angle: Real) {
+        self.lower_angle = Real::min(lower_angle, upper_angle);
+        self.upper_angle = Real::max(lower_angle, upper_angle);
+    }
+
+    fn relative_angular_velocity(&self, bodies: &SolverBodies) -> Real {
+        let (id1, id2) = self.point.ids;
+        bodies.angular_velocity(id2) - bodies.angular_velocity(id1)
+    }
+
+    fn apply_angular_impulse(&self, bodies: &mut SolverBodies, impulse: Real) {
+        let (id1, id2) = self.point.ids;
+        bodies.apply_angular_impulse(id1, -impulse);
+        bodies.apply_angular_impulse(id2, impulse);
+    }
+}
+
+impl Constraint for RevoluteJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        let inv_dt = step.inv_dt;
+        self.dt = if inv_dt > 0.0 {
+            1.0 / inv_dt
+        } else {
+            real(0.0)
+        };
+        self.point.bias_factor = self.bias_factor;
+        self.point.softness = self.softness;
+        // Ids for the angular rows, set again by the point joint
+        self.point.ids = (
+            step.bodies.id(&self.point.body1),
+            step.bodies.id(&self.point.body2),
+        );
+        let (id1, id2) = self.point.ids;
+        self.angle = self.follow_angle(step.bodies.rotation(id1), step.bodies.rotation(id2));
+        let angle = self.angle;
+
+        let inv_i = step.bodies.angular_inv_i(id1) + step.bodies.angular_inv_i(id2);
+        self.axial_mass = if inv_i > 0.0 { 1.0 / inv_i } else { real(0.0) };
+
+        if self.enable_limit {
+            let bias_factor = self.bias_factor;
+            self.lower_bias = LimitBias::new(angle - self.lower_angle, bias_factor, step);
+            self.upper_bias = LimitBias::new(self.upper_angle - angle, bias_factor, step);
+        } else {
+            self.lower_impulse = real(0.0);
+            self.upper_impulse = real(0.0);
+        }
+        if !self.enable_motor {
+            self.motor_impulse = real(0.0);
+        }
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            let impulse = self.motor_impulse + self.lower_impulse - self.upper_impulse;
+            self.apply_angular_impulse(step.bodies, impulse);
+        } else {
+            self.motor_impulse = real(0.0);
+            self.lower_impulse = real(0.0);
+            self.upper_impulse = real(0.0);
+        }
+
+        self.point.pre_step(step);
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let use_bias = step.use_bias;
+        if self.enable_motor {
+            let w = self.relative_angular_velocity(step.bodies) - self.motor_speed;
+            let impulse = -self.axial_mass * w;
+
+            // Clamp the accumulated impulse
+            let max_impulse = self.max_motor_torque * self.dt;
+            let old_impulse = self.motor_impulse;
+            self.motor_impulse = clamp(old_impulse + impulse, -max_impulse, max_impulse);
+            self.apply_angular_impulse(step.bodies, self.motor_impulse - old_impulse);
+        }
+
+        if self.enable_limit {
+            // Lower limit
+            {
+                let w = self.relative_angular_velocity(step.bodies);
+                let impulse =
+                    self.lower_bias
+                        .impulse(self.axial_mass, w, self.lower_impulse, use_bias);
+                let old_impulse = self.lower_impulse;
+                self.lower_impulse = Real::max(real(0.0), old_impulse + impulse);
+                self.apply_angular_impulse(step.bodies, self.lower_impulse - old_impulse);
+            }
+
+            // Upper limit
+            {
+                let w = -self.relative_angular_velocity(step.bodies);
+                let impulse =
+                    self.upper_bias
+                        .impulse(self.axial_mass, w, self.upper_impulse, use_bias);
+                let old_impulse = self.upper_impulse;
+                self.upper_impulse = Real::max(real(0.0), old_impulse + impulse);
+                self.apply_angular_impulse(step.bodies, -(self.upper_impulse - old_impulse));
+            }
+        }
+
+        self.point.apply_impulse(step);
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        self.point.reaction_force(inv_dt)
+    }
+
+    fn reaction_torque(&self, inv_dt: Real) -> Real {
+        inv_dt * (self.motor_impulse + self.lower_impulse - self.upper_impulse)
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        self.point.bodies()
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    fn solve_position(&mut self, step: &mut StepContext) -> bool {
+        self.point.solve_position(step)
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        self.point.get_joint_lines()
+    }
+}
@@ -0,0 +1,267 @@
+use crate::body::Body;
+use crate::math_utils::{Real, Rot, Transform, Vec2, cross_f_v, cross_v_v, real, unwind_angle};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Mass, forces and locks, not read by the solver loops
+#[derive(Clone, Copy)]
+struct Forcing {
+    mass: Real,
+    inv_mass: Real,
+    inv_i: Real,
+    force: Vec2,
+    torque: Real,
+    lock_translation_x: bool,
+    lock_translation_y: bool,
+    fixed_rotation: bool,
+}
+
+// Body state copied into contiguous arrays indexed by body id for the
+// duration of World::step, so contacts and joints solve without pointer
+// chasing or RefCell borrows. World bodies keep their serial number as id.
+// Constraints get ids for their bodies from `id`, which appends bodies
+// outside the world, like World::ground, on first use. Those are written
+// back after the step but never integrated.
+#[derive(Default)]
+pub struct SolverBodies {
+    handles: Vec<Rc<RefCell<Body>>>,
+    world_len: usize,
+    positions: Vec<Vec2>,
+    rotations: Vec<Real>,
+    rots: Vec<Rot>,
+    velocities: Vec<Vec2>,
+    angular_velocities: Vec<Real>,
+    // Split impulse pseudo-velocities, zero outside a step
+    bias_velocities: Vec<Vec2>,
+    bias_angular_velocities: Vec<Real>,
+    // Locks applied, see Body::linear_inv_mass and Body::angular_inv_i
+    inv_masses: Vec<Vec2>,
+    inv_is: Vec<Real>,
+    forcings: Vec<Forcing>,
+}
+
+impl SolverBodies {
+    pub(crate) fn load(&mut self, bodies: &[Rc<RefCell<Body>>]) {
+        self.handles.clear();
+        self.positions.clear();
+        self.rotations.clear();
+        self.rots.clear();
+        self.velocities.clear();
+        self.angular_velocities.clear();
+        self.bias_velocities.clear();
+        self.bias_angular_velocities.clear();
+        self.inv_masses.clear();
+        self.inv_is.clear();
+        self.forcings.clear();
+
+        for body in bodies {
+            self.push(body.clone());
+        }
+        self.world_len = bodies.len();
+    }
+
+    // Writes the state back to every body, the world ones and those added by `id`
+    pub(crate) fn store(&self) {
+        for (i, body) in self.handles.iter().enumerate() {
+            self.write(i, &mut body.borrow_mut());
+        }
+    }
+
+    // Id of `body` in the arrays. Call it in Constraint::pre_step and keep
+    // the id for the rest of the step, ids change between steps.
+    pub fn id(&mut self, body: &Rc<RefCell<Body>>) -> usize {
+        let serial_number = body.borrow().serial_number;
+        if self.handles[..self.world_len]
+            .get(serial_number)
+            .is_some_and(|b| Rc::ptr_eq(b, body))
+        {
+            return serial_number;
+        }
+        if let Some(i) = self.handles[self.world_len..]
+            .iter()
+            .position(|b| Rc::ptr_eq(b, body))
+        {
+            return self.world_len + i;
+        }
+        self.push(body.clone());
+        self.handles.len() - 1
+    }
+
+    fn push(&mut self, handle: Rc<RefCell<Body>>) {
+        {
+            let body = handle.borrow();
+            self.positions.push(body.position);
+            self.rotations.push(body.rotation);
+            self.rots.push(body.rot);
+            self.velocities.push(body.velocity);
+            self.angular_velocities.push(body.angular_velocity);
+            self.bias_velocities.push(Vec2::ZERO);
+            self.bias_angular_velocities.push(real(0.0));
+            self.inv_masses.push(body.linear_inv_mass());
+            self.inv_is.push(body.angular_inv_i());
+            self.forcings.push(Forcing {
+                mass: body.mass(),
+                inv_mass: body.inv_mass,
+                inv_i: body.inv_i,
+                force: body.force,
+                torque: body.torque,
+                lock_translation_x: body.lock_translation_x,
+                lock_translation_y: body.lock_translation_y,
+                fixed_rotation: body.fixed_rotation,
+            });
+        }
+        self.handles.push(handle);
+    }
+
+    fn write(&self, i: usize, body: &mut Body) {
+        body.position = self.positions[i];
+        body.rotation = self.rotations[i];
+        body.rot = self.rots[i];
+        body.velocity = self.velocities[i];
+        body.angular_velocity = self.angular_velocities[i];
+    }
+
+    pub(crate) fn integrate_forces(&mut self, gravity: Vec2, dt: Real) {
+        for (i, forcing) in self.forcings[..self.world_len].iter().enumerate() {
+            if forcing.inv_mass == 0.0 {
+                continue;
+            }
+            let velocity = &mut self.velocities[i];
+            *velocity += dt * (gravity + (forcing.inv_mass * forcing.force));
+            let angular_velocity = self.angular_velocities[i] + dt * forcing.inv_i * forcing.torque;
+            self.angular_velocities[i] = angular_velocity;
+
+            // Zero the velocity components of locked degrees of freedom
+            if forcing.lock_translation_x {
+                velocity.x = real(0.0);
+            }
+            if forcing.lock_translation_y {
+                velocity.y = real(0.0);
+            }
+            if forcing.fixed_rotation {
+                self.angular_velocities[i] = real(0.0);
+            }
+        }
+    }
+
+    pub(crate) fn integrate_velocities(&mut self, dt: Real) {
+        for i in 0..self.world_len {
+            let velocity = self.velocities[i] + self.bias_velocities[i];
+            let angular_velocity = self.angular_velocities[i] + self.bias_angular_velocities[i];
+
+            self.positions[i] += dt * velocity;
+            self.rotate_by(i, dt * angular_velocity);
+
+            self.bias_velocities[i] = Vec2::ZERO;
+            self.bias_angular_velocities[i] = real(0.0);
+        }
+    }
+
+    pub fn position(&self, i: usize) -> Vec2 {
+        self.positions[i]
+    }
+
+    // Angle in (-pi, pi], same as Body::rotation
+    pub fn rotation(&self, i: usize) -> Real {
+        self.rotations[i]
+    }
+
+    pub fn rot(&self, i: usize) -> Rot {
+        self.rots[i]
+    }
+
+    pub fn transform(&self, i: usize) -> Transform {
+        Transform::new(self.positions[i], self.rots[i])
+    }
+
+    pub fn velocity(&self, i: usize) -> Vec2 {
+        self.velocities[i]
+    }
+
+    pub fn set_velocity(&mut self, i: usize, velocity: Vec2) {
+        self.velocities[i] = velocity;
+    }
+
+    pub fn angular_velocity(&self, i: usize) -> Real {
+        self.angular_velocities[i]
+    }
+
+    pub fn set_angular_velocity(&mut self, i: usize, angular_velocity: Real) {
+        self.angular_velocities[i] = angular_velocity;
+    }
+
+    pub fn mass(&self, i: usize) -> Real {
+        self.forcings[i].mass
+    }
+
+    pub fn linear_inv_mass(&self, i: usize) -> Vec2 {
+        self.inv_masses[i]
+    }
+
+    pub fn angular_inv_i(&self, i: usize) -> Real {
+        self.inv_is[i]
+    }
+
+    // Inverse mass seen by an impulse along the unit direction `n`
+    pub fn inv_mass_along(&self, i: usize, n: Vec2) -> Real {
+        let inv_mass = self.inv_masses[i];
+        inv_mass.x * n.x * n.x + inv_mass.y * n.y * n.y
+    }
+
+    // Velocity of the body material at offset `r` from the center
+    pub fn velocity_at(&self, i: usize, r: Vec2) -> Vec2 {
+        self.velocities[i] + cross_f_v(self.angular_velocities[i], r)
+    }
+
+    pub(crate) fn bias_velocity_at(&self, i: usize, r: Vec2) -> Vec2 {
+        self.bias_velocities[i] + cross_f_v(self.bias_angular_velocities[i], r)
+    }
+
+    pub fn sub_velocity(&mut self, i: usize, r: Vec2, p: Vec2) {
+        let inv_mass = self.inv_masses[i];
+        self.velocities[i] -= Vec2::new(inv_mass.x * p.x, inv_mass.y * p.y);
+        self.angular_velocities[i] -= self.inv_is[i] * cross_v_v(r, p);
+    }
+
+    pub fn add_velocity(&mut self, i: usize, r: Vec2, p: Vec2) {
+        let inv_mass = self.inv_masses[i];
+        self.velocities[i] += Vec2::new(inv_mass.x * p.x, inv_mass.y * p.y);
+        self.angular_velocities[i] += self.inv_is[i] * cross_v_v(r, p);
+    }
+
+    pub(crate) fn sub_bias_velocity(&mut self, i: usize, r: Vec2, p: Vec2) {
+        let inv_mass = self.inv_masses[i];
+        self.bias_velocities[i] -= Vec2::new(inv_mass.x * p.x, inv_mass.y * p.y);
+        self.bias_angular_velocities[i] -= self.inv_is[i] * cross_v_v(r, p);
+    }
+
+    pub(crate) fn add_bias_velocity(&mut self, i: usize, r: Vec2, p: Vec2) {
+        let inv_mass = self.inv_masses[i];
+        self.bias_velocities[i] += Vec2::new(inv_mass.x * p.x, inv_mass.y * p.y);
+        self.bias_angular_velocities[i] += self.inv_is[i] * cross_v_v(r, p);
+    }
+
+    pub fn apply_angular_impulse(&mut self, i: usize, impulse: Real) {
+        self.angular_velocities[i] += self.inv_is[i] * impulse;
+    }
+
+    // Position impulses of the NGS pass move the body directly
+    pub fn sub_position(&mut self, i: usize, r: Vec2, p: Vec2) {
+        let inv_mass = self.inv_masses[i];
+        self.positions[i] -= Vec2::new(inv_mass.x * p.x, inv_mass.y * p.y);
+        self.rotate_by(i, -self.inv_is[i] * cross_v_v(r, p));
+    }
+
+    pub fn add_position(&mut self, i: usize, r: Vec2, p: Vec2) {
+        let inv_mass = self.inv_masses[i];
+        self.positions[i] += Vec2::new(inv_mass.x * p.x, inv_mass.y * p.y);
+        self.rotate_by(i, self.inv_is[i] * cross_v_v(r, p));
+    }
+
+    // Turns the body ignoring its inertia, the angle stays wrapped
+    pub fn rotate_by(&mut self, i: usize, angle: Real) {
+        self.rotations[i] = unwind_angle(self.rotations[i] + angle);
+        self.rots[i] = Rot::from_angle(self.rotations[i]);
+    }
+}
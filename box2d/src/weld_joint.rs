@@ -0,0 +1,211 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, Softness, StepContext};
+use crate::joint::Joint;
+use crate::math_utils::{PI, Real, Vec2, real, unwind_angle};
+use crate::solver_bodies::SolverBodies;
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Point joint that also locks the relative angle of the two bodies. With
+// `frequency_hz > 0` the angle is held by a damped spring instead.
+pub struct WeldJoint {
+    pub(crate) point: Joint,
+    reference_angle: Real,
+    angular_mass: Real,
+    gamma: Real,
+    bias: Real,
+    soft: Softness, // of the rigid angle
+    impulse: Real,  // accumulated angular impulse
+    pub frequency_hz: Real,
+    pub damping_ratio: Real,
+    pub bias_factor: Real,
+    pub softness: Real, // of the anchor, 0 means rigid
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
+}
+
+impl WeldJoint {
+    pub fn new(body1: Rc<RefCell<Body>>, body2: Rc<RefCell<Body>>, anchor: Vec2) -> Self {
+        let reference_angle = unwind_angle(body2.borrow().rotation - body1.borrow().rotation);
+        Self {
+            point: Joint::new(body1, body2, anchor),
+            reference_angle,
+            angular_mass: real(0.0),
+            gamma: real(0.0),
+            bias: real(0.0),
+            soft: Softness::RIGID,
+            impulse: real(0.0),
+            frequency_hz: real(0.0),
+            damping_ratio: real(0.0),
+            bias_factor: real(0.2),
+            softness: real(0.0),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
+        }
+    }
+
+    pub fn body1(&self) -> Rc<RefCell<Body>> {
+        self.point.body1.clone()
+    }
+
+    pub fn body2(&self) -> Rc<RefCell<Body>> {
+        self.point.body2.clone()
+    }
+
+    // Anchor in body1 coordinates
+    pub fn local_anchor1(&self) -> Vec2 {
+        self.point.local_anchor1
+    }
+
+    // Anchor in body2 coordinates
+    pub fn local_anchor2(&self) -> Vec2 {
+        self.point.local_anchor2
+    }
+
+    // Rotation of body2 relative to body1 away from the welded angle
+    pub fn angle(&self) -> Real {
+        unwind_angle(
+            self.point.body2.borrow().rotation
+                - self.point.body1.borrow().rotation
+                - self.reference_angle,
+        )
+    }
+
+    fn solver_angle(&self, bodies: &SolverBodies) -> Real {
+        let (id1, id2) = self.point.ids;
+        unwind_angle(bodies.rotation(id2) - bodies.rotation(id1) - self.reference_angle)
+    }
+
+    fn angular_inv_i(&self, bodies: &SolverBodies) -> Real {
+        let (id1, id2) = self.point.ids;
+        bodies.angular_inv_i(id1) + bodies.angular_inv_i(id2)
+    }
+
+    fn apply_angular_position(&self, bodies: &mut SolverBodies, impulse: Real) {
+        let (id1, id2) = self.point.ids;
+        let inv_i1 = bodies.angular_inv_i(id1);
+        bodies.rotate_by(id1, -inv_i1 * impulse);
+        let inv_i2 = bodies.angular_inv_i(id2);
+        bodies.rotate_by(id2, inv_i2 * impulse);
+    }
+
+    fn apply_angular_impulse(&self, bodies: &mut SolverBodies, impulse: Real) {
+        let (id1, id2) = self.point.ids;
+        bodies.apply_angular_impulse(id1, -impulse);
+        bodies.apply_angular_impulse(id2, impulse);
+    }
+}
+
+impl Constraint for WeldJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        let inv_dt = step.inv_dt;
+        self.point.bias_factor = self.bias_factor;
+        self.point.softness = self.softness;
+        // Ids for the angular row, set again by the point joint
+        self.point.ids = (
+            step.bodies.id(&self.point.body1),
+            step.bodies.id(&self.point.body2),
+        );
+        let angle = self.solver_angle(step.bodies);
+        let inv_i = self.angular_inv_i(step.bodies);
+        let mass = if inv_i > 0.0 { 1.0 / inv_i } else { real(0.0) };
+
+        if self.frequency_hz > 0.0 && inv_dt > 0.0 {
+            let dt = 1.0 / inv_dt;
+            // frequency in radians
+            let omega = 2.0 * PI * self.frequency_hz;
+            // damping coefficient
+            let d = 2.0 * mass * self.damping_ratio * omega;
+            // spring stiffness
+            let k = mass * omega * omega;
+
+            let gamma = dt * (d + dt * k);
+            self.gamma = if gamma > 0.0 { 1.0 / gamma } else { real(0.0) };
+            self.bias = angle * dt * k * self.gamma;
+            let inv_i = inv_i + self.gamma;
+            self.angular_mass = if inv_i > 0.0 { 1.0 / inv_i } else { real(0.0) };
+        } else {
+            self.gamma = real(0.0);
+            self.soft = if step.solve_positions {
+                Softness::RIGID
+            } else {
+                step.rigid_softness(self.bias_factor)
+            };
+            self.bias = self.soft.bias_rate * angle;
+            self.angular_mass = mass;
+        }
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            self.apply_angular_impulse(step.bodies, self.impulse);
+        } else {
+            self.impulse = real(0.0);
+        }
+
+        self.point.pre_step(step);
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        let (id1, id2) = self.point.ids;
+        let w = step.bodies.angular_velocity(id2) - step.bodies.angular_velocity(id1);
+        let impulse = if self.gamma > 0.0 {
+            // The spring is physical, it stays in the relax iteration
+            -self.angular_mass * (w + self.bias + self.gamma * self.impulse)
+        } else if step.use_bias {
+            self.soft
+                .impulse(self.angular_mass, w, self.bias, self.impulse)
+        } else {
+            -self.angular_mass * w
+        };
+        self.impulse += impulse;
+        self.apply_angular_impulse(step.bodies, impulse);
+
+        self.point.apply_impulse(step);
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        self.point.reaction_force(inv_dt)
+    }
+
+    fn reaction_torque(&self, inv_dt: Real) -> Real {
+        inv_dt * self.impulse
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        self.point.bodies()
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    fn solve_position(&mut self, step: &mut StepContext) -> bool {
+        const ANGULAR_SLOP: Real = real(2.0 / 180.0 * core::f64::consts::PI);
+        let mut angle_ok = true;
+        if self.frequency_hz <= 0.0 {
+            let angle = self.solver_angle(step.bodies);
+            let inv_i = self.angular_inv_i(step.bodies);
+            if inv_i > 0.0 {
+                self.apply_angular_position(step.bodies, -angle / inv_i);
+            }
+            angle_ok = angle.abs() <= ANGULAR_SLOP;
+        }
+        self.point.solve_position(step) && angle_ok
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        self.point.get_joint_lines()
+    }
+}
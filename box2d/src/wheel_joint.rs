@@ -0,0 +1,371 @@
+use crate::body::Body;
+use crate::constraint::{Constraint, LimitBias, Softness, StepContext};
+use crate::math_utils::{Mat22, PI, Real, Vec2, clamp, cross_f_v, cross_v_v, dot, real};
+use crate::solver_bodies::SolverBodies;
+use crate::world::WARM_STARTING;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Vehicle wheel: body2 (wheel) slides along a suspension axis fixed in body1
+// (chassis) and rotates freely. The suspension is a damped spring when
+// `frequency_hz > 0`, an optional motor drives the wheel rotation.
+pub struct WheelJoint {
+    local_anchor1: Vec2,
+    local_anchor2: Vec2,
+    local_axis: Vec2, // unit suspension axis in body1 frame
+    r1: Vec2,         // from body1 center to anchor2, impulses act on body1 there
+    r2: Vec2,
+    axis: Vec2,
+    perp: Vec2,
+    perp_mass: Real,
+    axial_mass: Real,
+    spring_mass: Real,
+    motor_mass: Real,
+    gamma: Real,
+    dt: Real,
+    perp_bias: Real,
+    perp_soft: Softness,
+    spring_bias: Real,
+    lower_bias: LimitBias,
+    upper_bias: LimitBias,
+    perp_impulse: Real,   // accumulated point-to-line impulse
+    spring_impulse: Real, // accumulated suspension impulse
+    motor_impulse: Real,  // accumulated motor impulse
+    lower_impulse: Real,  // accumulated lower limit impulse
+    upper_impulse: Real,  // accumulated upper limit impulse
+    ids: (usize, usize),
+    body1: Rc<RefCell<Body>>,
+    body2: Rc<RefCell<Body>>,
+    pub frequency_hz: Real,
+    pub damping_ratio: Real,
+    pub enable_limit: bool,
+    pub lower_translation: Real,
+    pub upper_translation: Real,
+    pub enable_motor: bool,
+    pub motor_speed: Real,
+    pub max_motor_torque: Real,
+    pub bias_factor: Real,
+    pub break_force: Option<Real>,
+    pub break_torque: Option<Real>,
+    pub collide_connected: bool,
+}
+
+impl WheelJoint {
+    // `axis` is normalized, panics if it is zero
+    pub fn new(
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor: Vec2,
+        axis: Vec2,
+    ) -> Self {
+        assert!(axis.length_squared() > 0.0, "joint axis must be non-zero");
+        let (local_anchor1, local_anchor2, local_axis) = {
+            let body1 = &body1.borrow();
+            let body2 = &body2.borrow();
+
+            let rot1t = Mat22::from(body1.rot).transpose();
+            let rot2t = Mat22::from(body2.rot).transpose();
+
+            (
+                rot1t * (anchor - body1.position),
+                rot2t * (anchor - body2.position),
+                rot1t * axis.normalize(),
+            )
+        };
+
+        Self {
+            local_anchor1,
+            local_anchor2,
+            local_axis,
+            r1: Vec2::default(),
+            r2: Vec2::default(),
+            axis: Vec2::default(),
+            perp: Vec2::default(),
+            perp_mass: real(0.0),
+            axial_mass: real(0.0),
+            spring_mass: real(0.0),
+            motor_mass: real(0.0),
+            gamma: real(0.0),
+            dt: real(0.0),
+            perp_bias: real(0.0),
+            perp_soft: Softness::RIGID,
+            spring_bias: real(0.0),
+            lower_bias: LimitBias::default(),
+            upper_bias: LimitBias::default(),
+            perp_impulse: real(0.0),
+            spring_impulse: real(0.0),
+            motor_impulse: real(0.0),
+            lower_impulse: real(0.0),
+            upper_impulse: real(0.0),
+            ids: (0, 0),
+            body1,
+            body2,
+            frequency_hz: real(2.0),
+            damping_ratio: real(0.7),
+            enable_limit: false,
+            lower_translation: real(0.0),
+            upper_translation: real(0.0),
+            enable_motor: false,
+            motor_speed: real(0.0),
+            max_motor_torque: real(0.0),
+            bias_factor: real(0.2),
+            break_force: None,
+            break_torque: None,
+            collide_connected: false,
+        }
+    }
+
+    // Suspension compression: offset of anchor2 from anchor1 along the axis
+    pub fn translation(&self) -> Real {
+        let body1 = self.body1.borrow();
+        let body2 = self.body2.borrow();
+        let rot1 = Mat22::from(body1.rot);
+        let p1 = body1.position + rot1 * self.local_anchor1;
+        let p2 = body2.transform() * self.local_anchor2;
+        dot(rot1 * self.local_axis, p2 - p1)
+    }
+
+    // Wheel spin relative to the chassis
+    pub fn angular_speed(&self) -> Real {
+        self.body2.borrow().angular_velocity - self.body1.borrow().angular_velocity
+    }
+
+    pub fn set_limits(&mut self, lower_translation: Real, upper_translation: Real) {
+        self.lower_translation = Real::min(lower_translation, upper_translation);
+        self.upper_translation = Real::max(lower_translation, upper_translation);
+    }
+
+    // Relative velocity of anchor2 and body1 material at anchor2 along `dir`
+    fn relative_velocity(&self, bodies: &SolverBodies, dir: Vec2) -> Real {
+        let (id1, id2) = self.ids;
+        let dv = bodies.velocity_at(id2, self.r2) - bodies.velocity_at(id1, self.r1);
+        dot(dir, dv)
+    }
+
+    fn relative_angular_velocity(&self, bodies: &SolverBodies) -> Real {
+        let (id1, id2) = self.ids;
+        bodies.angular_velocity(id2) - bodies.angular_velocity(id1)
+    }
+
+    // Apply a linear impulse `p` at the anchors and an extra angular impulse
+    fn apply(&self, bodies: &mut SolverBodies, p: Vec2, angular: Real) {
+        let (id1, id2) = self.ids;
+        bodies.sub_velocity(id1, self.r1, p);
+        bodies.apply_angular_impulse(id1, -angular);
+        bodies.add_velocity(id2, self.r2, p);
+        bodies.apply_angular_impulse(id2, angular);
+    }
+
+    // One-dimensional accumulated impulse along the axis, clamped to be positive
+    fn solve_limit(
+        &self,
+        step: &mut StepContext,
+        sign: Real,
+        bias: LimitBias,
+        accumulated: Real,
+    ) -> Real {
+        let vn = sign * self.relative_velocity(step.bodies, self.axis);
+        let impulse = bias.impulse(self.axial_mass, vn, accumulated, step.use_bias);
+        let new_impulse = Real::max(real(0.0), accumulated + impulse);
+        self.apply(
+            step.bodies,
+            sign * (new_impulse - accumulated) * self.axis,
+            real(0.0),
+        );
+        new_impulse
+    }
+}
+
+impl Constraint for WheelJoint {
+    fn pre_step(&mut self, step: &mut StepContext) {
+        let inv_dt = step.inv_dt;
+        self.dt = if inv_dt > 0.0 {
+            1.0 / inv_dt
+        } else {
+            real(0.0)
+        };
+        self.ids = (step.bodies.id(&self.body1), step.bodies.id(&self.body2));
+        let (id1, id2) = self.ids;
+        let translation = {
+            let bodies = &*step.bodies;
+            let rot1 = Mat22::from(bodies.rot(id1));
+            let r1 = rot1 * self.local_anchor1;
+            self.r2 = Mat22::from(bodies.rot(id2)) * self.local_anchor2;
+            let d = (bodies.position(id2) + self.r2) - (bodies.position(id1) + r1);
+            self.r1 = d + r1;
+
+            self.axis = rot1 * self.local_axis;
+            self.perp = cross_f_v(real(1.0), self.axis);
+
+            let inv_i1 = bodies.angular_inv_i(id1);
+            let inv_i2 = bodies.angular_inv_i(id2);
+
+            // Point to line constraint
+            let s1 = cross_v_v(self.r1, self.perp);
+            let s2 = cross_v_v(self.r2, self.perp);
+            let k_perp = bodies.inv_mass_along(id1, self.perp)
+                + bodies.inv_mass_along(id2, self.perp)
+                + inv_i1 * s1 * s1
+                + inv_i2 * s2 * s2;
+            self.perp_mass = if k_perp > 0.0 {
+                1.0 / k_perp
+            } else {
+                real(0.0)
+            };
+            self.perp_soft = step.rigid_softness(self.bias_factor);
+            self.perp_bias = self.perp_soft.bias_rate * dot(self.perp, d);
+
+            // Suspension spring and limits
+            let a1 = cross_v_v(self.r1, self.axis);
+            let a2 = cross_v_v(self.r2, self.axis);
+            let k_axial = bodies.inv_mass_along(id1, self.axis)
+                + bodies.inv_mass_along(id2, self.axis)
+                + inv_i1 * a1 * a1
+                + inv_i2 * a2 * a2;
+            self.axial_mass = if k_axial > 0.0 {
+                1.0 / k_axial
+            } else {
+                real(0.0)
+            };
+
+            let translation = dot(self.axis, d);
+            self.spring_mass = real(0.0);
+            self.gamma = real(0.0);
+            self.spring_bias = real(0.0);
+            if self.frequency_hz > 0.0 && self.axial_mass > 0.0 && inv_dt > 0.0 {
+                // frequency in radians
+                let omega = 2.0 * PI * self.frequency_hz;
+                // damping coefficient
+                let damping = 2.0 * self.axial_mass * self.damping_ratio * omega;
+                // spring stiffness
+                let k = self.axial_mass * omega * omega;
+
+                let gamma = self.dt * (damping + self.dt * k);
+                self.gamma = if gamma > 0.0 { 1.0 / gamma } else { real(0.0) };
+                self.spring_bias = translation * self.dt * k * self.gamma;
+                self.spring_mass = 1.0 / (k_axial + self.gamma);
+            }
+
+            // Rotational motor
+            let k_motor = inv_i1 + inv_i2;
+            self.motor_mass = if k_motor > 0.0 {
+                1.0 / k_motor
+            } else {
+                real(0.0)
+            };
+
+            translation
+        };
+
+        if self.spring_mass == 0.0 {
+            self.spring_impulse = real(0.0);
+        }
+        if self.enable_limit {
+            self.lower_bias =
+                LimitBias::new(translation - self.lower_translation, self.bias_factor, step);
+            self.upper_bias =
+                LimitBias::new(self.upper_translation - translation, self.bias_factor, step);
+        } else {
+            self.lower_impulse = real(0.0);
+            self.upper_impulse = real(0.0);
+        }
+        if !self.enable_motor {
+            self.motor_impulse = real(0.0);
+        }
+
+        if WARM_STARTING {
+            // Apply accumulated impulse.
+            let axial = self.spring_impulse + self.lower_impulse - self.upper_impulse;
+            let p = (self.perp_impulse * self.perp) + (axial * self.axis);
+            self.apply(step.bodies, p, self.motor_impulse);
+        } else {
+            self.perp_impulse = real(0.0);
+            self.spring_impulse = real(0.0);
+            self.motor_impulse = real(0.0);
+            self.lower_impulse = real(0.0);
+            self.upper_impulse = real(0.0);
+        }
+    }
+
+    fn apply_impulse(&mut self, step: &mut StepContext) {
+        // Suspension spring
+        if self.spring_mass > 0.0 {
+            let vn = self.relative_velocity(step.bodies, self.axis);
+            let impulse =
+                -self.spring_mass * (vn + self.spring_bias + self.gamma * self.spring_impulse);
+            self.spring_impulse += impulse;
+            self.apply(step.bodies, impulse * self.axis, real(0.0));
+        }
+
+        if self.enable_motor {
+            let w = self.relative_angular_velocity(step.bodies) - self.motor_speed;
+            let impulse = -self.motor_mass * w;
+
+            // Clamp the accumulated impulse
+            let max_impulse = self.max_motor_torque * self.dt;
+            let old_impulse = self.motor_impulse;
+            self.motor_impulse = clamp(old_impulse + impulse, -max_impulse, max_impulse);
+            self.apply(
+                step.bodies,
+                Vec2::default(),
+                self.motor_impulse - old_impulse,
+            );
+        }
+
+        if self.enable_limit {
+            self.lower_impulse =
+                self.solve_limit(step, real(1.0), self.lower_bias, self.lower_impulse);
+            self.upper_impulse =
+                self.solve_limit(step, real(-1.0), self.upper_bias, self.upper_impulse);
+        }
+
+        // Point to line constraint
+        let vn = self.relative_velocity(step.bodies, self.perp);
+        let impulse = if step.use_bias {
+            self.perp_soft
+                .impulse(self.perp_mass, vn, self.perp_bias, self.perp_impulse)
+        } else {
+            -self.perp_mass * vn
+        };
+        self.perp_impulse += impulse;
+        self.apply(step.bodies, impulse * self.perp, real(0.0));
+    }
+
+    fn reaction_force(&self, inv_dt: Real) -> Vec2 {
+        let axial = self.spring_impulse + self.lower_impulse - self.upper_impulse;
+        inv_dt * ((self.perp_impulse * self.perp) + (axial * self.axis))
+    }
+
+    fn reaction_torque(&self, inv_dt: Real) -> Real {
+        inv_dt * self.motor_impulse
+    }
+
+    fn break_force(&self) -> Option<Real> {
+        self.break_force
+    }
+
+    fn break_torque(&self) -> Option<Real> {
+        self.break_torque
+    }
+
+    fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+        vec![self.body1.clone(), self.body2.clone()]
+    }
+
+    fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    fn get_joint_lines(&self) -> Vec<(Vec2, Vec2)> {
+        let b1 = self.body1.borrow();
+        let b2 = self.body2.borrow();
+        let x1 = b1.position;
+        let p1 = b1.transform() * self.local_anchor1;
+        let x2 = b2.position;
+        let p2 = b2.transform() * self.local_anchor2;
+
+        vec![(x1, p1), (p1, p2), (x2, p2)]
+    }
+}
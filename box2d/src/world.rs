@@ -1,32 +1,98 @@
 use crate::arbiter::Arbiter;
 use crate::body::Body;
+use crate::constraint::{Constraint, JointEdge, MAX_LINEAR_CORRECTION, Softness, StepContext};
+use crate::distance_joint::DistanceJoint;
+use crate::gear_joint::{GearConnection, GearJoint};
 use crate::joint::Joint;
-use crate::math_utils::Vec2;
+use crate::math_utils::{Aabb, Real, Vec2, real};
+use crate::motor_joint::MotorJoint;
+use crate::mouse_joint::MouseJoint;
+use crate::prismatic_joint::PrismaticJoint;
+use crate::pulley_joint::PulleyJoint;
+use crate::revolute_joint::RevoluteJoint;
+use crate::solver_bodies::SolverBodies;
+use crate::weld_joint::WeldJoint;
+use crate::wheel_joint::WheelJoint;
 
-use std::cell::RefCell;
-use std::collections::BTreeMap;
-use std::rc::Rc;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
 
 pub struct World {
     bodies: Vec<Rc<RefCell<Body>>>,
-    joints: Vec<Rc<RefCell<Joint>>>,
+    joints: Vec<Rc<RefCell<dyn Constraint>>>,
+    broken_joints: Vec<Rc<RefCell<dyn Constraint>>>,
     arbiters: BTreeMap<(usize, usize), Arbiter>,
+    ground: Rc<RefCell<Body>>,
     gravity: Vec2,
     iterations: i32,
+    position_correction: PositionCorrection,
+    position_iterations: i32,
+    linear_slop: Real,
+    block_solver: bool,
+    solver_mode: SolverMode,
+    solver_bodies: SolverBodies,
 }
 
 pub static ACCUMULATE_IMPULSES: bool = true;
 pub static WARM_STARTING: bool = true;
 pub static POSITION_CORRECTION: bool = true;
 
+// How contacts remove penetration when POSITION_CORRECTION is on
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PositionCorrection {
+    // Position error is fed into the velocity bias
+    #[default]
+    Baumgarte,
+    // Position error is solved with separate pseudo-velocities that are
+    // dropped after the step, so correction adds no kinetic energy
+    SplitImpulse,
+    // After integration contacts and joints implementing solve_position
+    // are corrected directly on positions, up to `position_iterations` times
+    NonlinearGaussSeidel,
+}
+
+// How World::step solves the velocity constraints
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SolverMode {
+    // `iterations` sequential impulse passes over the whole step
+    #[default]
+    Iterations,
+    // Box2D v3 soft step: the step is split into `substeps`, each with one
+    // biased and one relaxing iteration. Contacts are springs given by
+    // `contact_hertz` (e.g. 30) and `contact_damping_ratio` (e.g. 10), rigid
+    // joint rows by `joint_hertz` (e.g. 60) and `joint_damping_ratio`
+    // (e.g. 2). PositionCorrection and the block solver do not apply.
+    SoftStep {
+        substeps: i32,
+        contact_hertz: Real,
+        contact_damping_ratio: Real,
+        joint_hertz: Real,
+        joint_damping_ratio: Real,
+    },
+}
+
 impl World {
     pub fn new(gravity: Vec2, iterations: i32) -> Self {
+        let mut ground = Body::default();
+        ground.serial_number = usize::MAX;
+
         Self {
             bodies: Vec::new(),
             joints: Vec::new(),
+            broken_joints: Vec::new(),
             arbiters: BTreeMap::new(),
+            ground: Rc::new(RefCell::new(ground)),
             gravity,
             iterations,
+            position_correction: PositionCorrection::default(),
+            position_iterations: 3,
+            linear_slop: real(0.01),
+            block_solver: false,
+            solver_mode: SolverMode::default(),
+            solver_bodies: SolverBodies::default(),
         }
     }
 
@@ -34,10 +100,46 @@ impl World {
         return &self.bodies;
     }
 
+    // Static body at the origin for pinning joints to fixed world points.
+    // It is not in `bodies`, so it never collides or gets drawn.
+    pub fn ground(&self) -> Rc<RefCell<Body>> {
+        self.ground.clone()
+    }
+
     pub fn bodies_size(&self) -> usize {
         self.bodies.len()
     }
 
+    pub fn position_correction(&self) -> PositionCorrection {
+        self.position_correction
+    }
+
+    pub fn set_position_correction(&mut self, position_correction: PositionCorrection) {
+        self.position_correction = position_correction;
+    }
+
+    pub fn set_position_iterations(&mut self, position_iterations: i32) {
+        self.position_iterations = position_iterations;
+    }
+
+    pub fn solver_mode(&self) -> SolverMode {
+        self.solver_mode
+    }
+
+    pub fn set_solver_mode(&mut self, solver_mode: SolverMode) {
+        self.solver_mode = solver_mode;
+    }
+
+    // Solve the two normal impulses of box-box contacts together
+    pub fn set_block_solver(&mut self, block_solver: bool) {
+        self.block_solver = block_solver;
+    }
+
+    // Penetration and joint error the NGS pass leaves in place
+    pub fn set_linear_slop(&mut self, linear_slop: Real) {
+        self.linear_slop = linear_slop;
+    }
+
     pub fn get_collide_points(&self) -> Vec<Vec2> {
         let mut res = Vec::new();
         for (_, arb) in &self.arbiters {
@@ -67,95 +169,524 @@ impl World {
         anchor: Vec2,
     ) -> Rc<RefCell<Joint>> {
         let joint = Rc::new(RefCell::new(Joint::new(body1, body2, anchor)));
-        self.joints.push(joint.clone());
+        self.push_joint(joint.clone());
         joint
     }
 
+    pub fn add_revolute_joint(
+        &mut self,
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor: Vec2,
+    ) -> Rc<RefCell<RevoluteJoint>> {
+        let joint = Rc::new(RefCell::new(RevoluteJoint::new(body1, body2, anchor)));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    pub fn add_prismatic_joint(
+        &mut self,
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor: Vec2,
+        axis: Vec2,
+    ) -> Rc<RefCell<PrismaticJoint>> {
+        let joint = Rc::new(RefCell::new(PrismaticJoint::new(
+            body1, body2, anchor, axis,
+        )));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    pub fn add_weld_joint(
+        &mut self,
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor: Vec2,
+    ) -> Rc<RefCell<WeldJoint>> {
+        let joint = Rc::new(RefCell::new(WeldJoint::new(body1, body2, anchor)));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    pub fn add_wheel_joint(
+        &mut self,
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor: Vec2,
+        axis: Vec2,
+    ) -> Rc<RefCell<WheelJoint>> {
+        let joint = Rc::new(RefCell::new(WheelJoint::new(body1, body2, anchor, axis)));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    pub fn add_mouse_joint(
+        &mut self,
+        body: Rc<RefCell<Body>>,
+        target: Vec2,
+    ) -> Rc<RefCell<MouseJoint>> {
+        let joint = Rc::new(RefCell::new(MouseJoint::new(body, target)));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    pub fn add_distance_joint(
+        &mut self,
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor1: Vec2,
+        anchor2: Vec2,
+    ) -> Rc<RefCell<DistanceJoint>> {
+        let joint = Rc::new(RefCell::new(DistanceJoint::new(
+            body1, body2, anchor1, anchor2,
+        )));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    // Rope over two fixed pulleys, `ground_anchors` and `anchors` are world points
+    pub fn add_pulley_joint(
+        &mut self,
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        ground_anchors: [Vec2; 2],
+        anchors: [Vec2; 2],
+        ratio: Real,
+    ) -> Rc<RefCell<PulleyJoint>> {
+        let joint = Rc::new(RefCell::new(PulleyJoint::new(
+            body1,
+            body2,
+            ground_anchors,
+            anchors,
+            ratio,
+        )));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    pub fn add_gear_joint(
+        &mut self,
+        joint1: GearConnection,
+        joint2: GearConnection,
+        ratio: Real,
+    ) -> Rc<RefCell<GearJoint>> {
+        let joint = Rc::new(RefCell::new(GearJoint::new(joint1, joint2, ratio)));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    pub fn add_motor_joint(
+        &mut self,
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        max_force: Real,
+        max_torque: Real,
+    ) -> Rc<RefCell<MotorJoint>> {
+        let joint = Rc::new(RefCell::new(MotorJoint::new(
+            body1, body2, max_force, max_torque,
+        )));
+        self.push_joint(joint.clone());
+        joint
+    }
+
+    // Registers a user-defined constraint, also accepts `Box<dyn Constraint>`
+    pub fn add_constraint<T: Constraint + 'static>(&mut self, constraint: T) -> Rc<RefCell<T>> {
+        let constraint = Rc::new(RefCell::new(constraint));
+        self.push_joint(constraint.clone());
+        constraint
+    }
+
+    pub fn remove_joint<T: ?Sized>(&mut self, joint: &Rc<RefCell<T>>) {
+        let Some(index) = self
+            .joints
+            .iter()
+            .position(|j| core::ptr::addr_eq(Rc::as_ptr(j), Rc::as_ptr(joint)))
+        else {
+            return;
+        };
+        let joint = self.joints.remove(index);
+        Self::unlink_joint(&joint);
+        self.break_coupled(vec![joint]);
+    }
+
+    // Store the joint and link every pair of its connected bodies with a joint edge
+    fn push_joint(&mut self, joint: Rc<RefCell<dyn Constraint>>) {
+        let bodies = joint.borrow().connected_bodies();
+        for body in &bodies {
+            for other in &bodies {
+                if !Rc::ptr_eq(body, other) {
+                    body.borrow_mut().joint_edges.push(JointEdge {
+                        other: Rc::downgrade(other),
+                        joint: Rc::downgrade(&joint),
+                    });
+                }
+            }
+        }
+        self.joints.push(joint);
+    }
+
+    fn unlink_joint(joint: &Rc<RefCell<dyn Constraint>>) {
+        for body in joint.borrow().connected_bodies() {
+            body.borrow_mut()
+                .joint_edges
+                .retain(|edge| !core::ptr::addr_eq(edge.joint.as_ptr(), Rc::as_ptr(joint)));
+        }
+    }
+
+    // Joints removed by World::step since the last call
+    pub fn take_broken_joints(&mut self) -> Vec<Rc<RefCell<dyn Constraint>>> {
+        core::mem::take(&mut self.broken_joints)
+    }
+
     pub fn clear(&mut self) {
+        for joint in &self.joints {
+            Self::unlink_joint(joint);
+        }
         self.bodies.clear();
         self.joints.clear();
+        self.broken_joints.clear();
+        self.arbiters.clear();
     }
 
-    pub fn step(&mut self, dt: f32) {
-        let inv_dt = if dt > 0.0 { 1.0 / dt } else { 0.0 };
-
+    pub fn step(&mut self, dt: Real) {
         // Determine overlapping bodies and update contact points.
         self.broad_phase();
 
-        // Integrate forces.
-        for body in &mut self.bodies {
-            let inv_mass = body.borrow().inv_mass;
-            let force = body.borrow().force;
-            let mut body = body.borrow_mut();
+        self.solver_bodies.load(&self.bodies);
 
-            if inv_mass == 0.0 {
-                continue;
-            }
-            body.velocity += dt * (self.gravity + (inv_mass * force));
-            body.angular_velocity += dt * body.inv_i * body.torque;
+        match self.solver_mode {
+            SolverMode::Iterations => self.step_iterations(dt),
+            SolverMode::SoftStep {
+                substeps,
+                contact_hertz,
+                contact_damping_ratio,
+                joint_hertz,
+                joint_damping_ratio,
+            } => self.soft_step(
+                dt,
+                substeps,
+                (contact_hertz, contact_damping_ratio),
+                (joint_hertz, joint_damping_ratio),
+            ),
         }
 
+        self.solver_bodies.store();
+        self.clear_forces();
+    }
+
+    fn step_iterations(&mut self, dt: Real) {
+        let inv_dt = if dt > 0.0 { 1.0 / dt } else { real(0.0) };
+
+        self.solver_bodies.integrate_forces(self.gravity, dt);
+
         // Perform pre-steps.
         for (_, arb) in &mut self.arbiters {
-            arb.pre_step(inv_dt);
+            arb.pre_step(
+                &mut self.solver_bodies,
+                inv_dt,
+                self.position_correction,
+                self.block_solver,
+            );
         }
 
-        for joint in &mut self.joints {
-            let mut joint = joint.borrow_mut();
-            joint.pre_step(inv_dt);
+        let solve_positions = self.position_correction == PositionCorrection::NonlinearGaussSeidel
+            && POSITION_CORRECTION;
+        let mut step = StepContext {
+            bodies: &mut self.solver_bodies,
+            inv_dt,
+            solve_positions,
+            softness: None,
+            substep: 0,
+            use_bias: true,
+            linear_slop: self.linear_slop,
+        };
+        for joint in &self.joints {
+            joint.borrow_mut().pre_step(&mut step);
         }
 
         // Perform iterations
         for _ in 0..self.iterations {
             for (_, arb) in &mut self.arbiters {
-                arb.apply_impulse();
+                arb.apply_impulse(step.bodies);
             }
-            for joint in &mut self.joints {
-                let mut joint = joint.borrow_mut();
-                joint.apply_impulse();
+            for joint in &self.joints {
+                joint.borrow_mut().apply_impulse(&mut step);
             }
         }
 
-        // Integrate Velocities
+        self.break_joints(inv_dt);
+
+        if self.position_correction == PositionCorrection::SplitImpulse {
+            for _ in 0..self.iterations {
+                for arb in self.arbiters.values_mut() {
+                    arb.apply_position_impulse(&mut self.solver_bodies);
+                }
+            }
+        }
+
+        self.solver_bodies.integrate_velocities(dt);
+
+        if solve_positions {
+            self.solve_positions(inv_dt);
+        }
+    }
+
+    // `contact` and `joint` are (hertz, damping ratio) of the soft rows
+    fn soft_step(&mut self, dt: Real, substeps: i32, contact: (Real, Real), joint: (Real, Real)) {
+        let substeps = substeps.max(1);
+        let h = dt / real(substeps as f64);
+        let inv_h = if h > 0.0 { 1.0 / h } else { real(0.0) };
+
+        // Contacts stiffer than a quarter of the substep rate are not stable,
+        // joints are allowed twice that like in Box2D v3
+        let contact_hertz = Real::min(contact.0, real(0.25) * inv_h);
+        let softness = Softness::new(contact_hertz, contact.1, h);
+        let joint_hertz = Real::min(joint.0, real(0.5) * inv_h);
+        let joint_softness = Softness::new(joint_hertz, joint.1, h);
+        let max_bias_velocity = MAX_LINEAR_CORRECTION * self.linear_slop * inv_h;
+
+        for arb in self.arbiters.values_mut() {
+            arb.prepare_soft_step(&self.solver_bodies);
+        }
+
+        for substep in 0..substeps {
+            self.solver_bodies.integrate_forces(self.gravity, h);
+
+            let mut step = StepContext {
+                bodies: &mut self.solver_bodies,
+                inv_dt: inv_h,
+                solve_positions: false,
+                softness: Some(joint_softness),
+                substep,
+                use_bias: true,
+                linear_slop: self.linear_slop,
+            };
+            for joint in &self.joints {
+                joint.borrow_mut().pre_step(&mut step);
+            }
+            for arb in self.arbiters.values_mut() {
+                arb.warm_start(step.bodies);
+            }
+
+            // Solve with soft contacts pushing out penetration
+            for arb in self.arbiters.values_mut() {
+                arb.solve_soft_step(step.bodies, inv_h, Some(softness), max_bias_velocity);
+            }
+            for joint in &self.joints {
+                joint.borrow_mut().apply_impulse(&mut step);
+            }
+
+            step.bodies.integrate_velocities(h);
+
+            // Relax: remove the velocity added by the joint and contact bias
+            step.use_bias = false;
+            for joint in &self.joints {
+                joint.borrow_mut().apply_impulse(&mut step);
+            }
+            for arb in self.arbiters.values_mut() {
+                arb.solve_soft_step(step.bodies, inv_h, None, max_bias_velocity);
+            }
+        }
+
+        // Accumulated impulses belong to the last substep
+        self.break_joints(inv_h);
+    }
+
+    fn clear_forces(&mut self) {
         for body in &mut self.bodies {
-            let velocity = body.borrow().velocity;
-            let angular_velocity = body.borrow().angular_velocity;
             let mut body = body.borrow_mut();
+            body.force.set(real(0.0), real(0.0));
+            body.torque = real(0.0);
+        }
+    }
 
-            body.position += dt * velocity;
-            body.rotation += dt * angular_velocity;
+    // Remove overloaded joints
+    fn break_joints(&mut self, inv_dt: Real) {
+        let first_broken = self.broken_joints.len();
+        let broken_joints = &mut self.broken_joints;
+        self.joints.retain(|joint| {
+            let broken = joint.borrow().is_broken(inv_dt);
+            if broken {
+                Self::unlink_joint(joint);
+                broken_joints.push(joint.clone());
+            }
+            !broken
+        });
+        self.break_coupled(self.broken_joints[first_broken..].to_vec());
+    }
 
-            body.force.set(0.0, 0.0);
-            body.torque = 0.0;
+    // Break the joints coupling any of `removed`, e.g. gears, and in turn
+    // the joints coupling those
+    fn break_coupled(&mut self, mut removed: Vec<Rc<RefCell<dyn Constraint>>>) {
+        while let Some(removed_joint) = removed.pop() {
+            let broken_joints = &mut self.broken_joints;
+            self.joints.retain(|joint| {
+                let broken = joint.borrow().coupled_joints().iter().any(|coupled| {
+                    core::ptr::addr_eq(Rc::as_ptr(coupled), Rc::as_ptr(&removed_joint))
+                });
+                if broken {
+                    Self::unlink_joint(joint);
+                    broken_joints.push(joint.clone());
+                    removed.push(joint.clone());
+                }
+                !broken
+            });
+        }
+    }
+
+    fn solve_positions(&mut self, inv_dt: Real) {
+        let mut step = StepContext {
+            bodies: &mut self.solver_bodies,
+            inv_dt,
+            solve_positions: true,
+            softness: None,
+            substep: 0,
+            use_bias: true,
+            linear_slop: self.linear_slop,
+        };
+        for _ in 0..self.position_iterations {
+            let mut min_separation: Real = real(0.0);
+            for arb in self.arbiters.values_mut() {
+                min_separation =
+                    min_separation.min(arb.solve_position(step.bodies, self.linear_slop));
+            }
+            let mut joints_ok = true;
+            for joint in &self.joints {
+                joints_ok &= joint.borrow_mut().solve_position(&mut step);
+            }
+
+            // Stop once the remaining error is small
+            if min_separation >= -3.0 * self.linear_slop && joints_ok {
+                break;
+            }
         }
     }
 
     fn broad_phase(&mut self) {
-        // O(n^2) broad-phase
-        for i in 0..self.bodies.len() {
+        // Sort and sweep on x. Boxes are fattened by the slop so touching
+        // pairs are not lost to round-off.
+        let margin = Vec2::new(self.linear_slop, self.linear_slop);
+        let bounds: Vec<Aabb> = self
+            .bodies
+            .iter()
+            .map(|body| {
+                let body = body.borrow();
+                let aabb = Aabb::from_box(body.transform(), real(0.5) * body.width);
+                Aabb::new(aabb.lower - margin, aabb.upper + margin)
+            })
+            .collect();
+        let mut order: Vec<usize> = (0..self.bodies.len()).collect();
+        order.sort_unstable_by(|&a, &b| {
+            bounds[a]
+                .lower
+                .x
+                .partial_cmp(&bounds[b].lower.x)
+                .unwrap_or(core::cmp::Ordering::Equal)
+        });
+
+        let mut pairs = Vec::new();
+        for (k, &i) in order.iter().enumerate() {
+            for &j in &order[k + 1..] {
+                if bounds[j].lower.x > bounds[i].upper.x {
+                    break;
+                }
+                if bounds[i].overlaps(bounds[j]) {
+                    pairs.push((i.min(j), i.max(j)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+
+        // Bodies that stopped overlapping lose their contacts
+        self.arbiters
+            .retain(|key, _| pairs.binary_search(key).is_ok());
+
+        for (i, j) in pairs {
             let bi = self.bodies[i].borrow();
-            for j in (i + 1)..self.bodies.len() {
-                let bj = self.bodies[j].borrow();
+            let bj = self.bodies[j].borrow();
+
+            if bi.inv_mass == 0.0 && bj.inv_mass == 0.0 {
+                continue;
+            }
+            if !bi.collides_with(&self.bodies[j]) {
+                self.arbiters.remove(&(bi.serial_number, bj.serial_number));
+                continue;
+            }
+            let (new_arb, new_num_contacts) =
+                Arbiter::create_arbiter_contacts(self.bodies[i].clone(), self.bodies[j].clone());
+            let key = new_arb.get_key();
+
+            if new_num_contacts > 0 {
+                if let Some(arb) = self.arbiters.get_mut(&key) {
+                    arb.update(&new_arb);
+                } else {
+                    self.arbiters.insert(key, new_arb);
+                }
+            } else {
+                self.arbiters.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                if bi.inv_mass == 0.0 && bj.inv_mass == 0.0 {
+    // Boxes of mixed sizes and angles scattered over a static floor, many
+    // of them overlapping
+    fn scattered_world() -> World {
+        let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), 10);
+        let floor = Body::new(real(40.0), real(1.0), Real::MAX, real(0.0), real(-0.5));
+        world.add_body(Rc::new(RefCell::new(floor)));
+
+        let mut seed: u32 = 12345;
+        let mut next = || {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            real((seed >> 8) as f64 / (1u32 << 24) as f64)
+        };
+        for _ in 0..200 {
+            let (w, h) = (real(0.3) + next(), real(0.3) + next());
+            let (x, y) = (real(30.0) * next() - real(15.0), real(6.0) * next());
+            let mut body = Body::new(w, h, real(1.0), x, y);
+            body.set_rotation(real(3.0) * next());
+            world.add_body(Rc::new(RefCell::new(body)));
+        }
+        world
+    }
+
+    // Keys of the pairs with contacts, testing every pair of bodies
+    fn touching_pairs(world: &World) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (i, bi) in world.bodies.iter().enumerate() {
+            for bj in &world.bodies[i + 1..] {
+                if bi.borrow().inv_mass == 0.0 && bj.borrow().inv_mass == 0.0 {
                     continue;
                 }
-                let (new_arb, new_num_contacts) = Arbiter::create_arbiter_contacts(
-                    self.bodies[i].clone(),
-                    self.bodies[j].clone(),
-                );
-                let key = new_arb.get_key();
-
-                if new_num_contacts > 0 {
-                    if let Some(arb) = self.arbiters.get_mut(&key) {
-                        arb.update(&new_arb);
-                    } else {
-                        self.arbiters.insert(key, new_arb);
-                    }
-                } else {
-                    self.arbiters.remove(&key);
+                let (arb, num_contacts) = Arbiter::create_arbiter_contacts(bi.clone(), bj.clone());
+                if num_contacts > 0 {
+                    pairs.push(arb.get_key());
                 }
             }
         }
+        pairs
+    }
+
+    #[test]
+    fn sweep_finds_every_touching_pair() {
+        let mut world = scattered_world();
+        for _ in 0..3 {
+            world.broad_phase();
+            let expected = touching_pairs(&world);
+            assert!(!expected.is_empty());
+            let found: Vec<(usize, usize)> = world.arbiters.keys().copied().collect();
+            assert_eq!(found, expected);
+
+            // Compare again once the boxes have fallen and piled up
+            for _ in 0..30 {
+                world.step(real(1.0 / 60.0));
+            }
+        }
     }
 }
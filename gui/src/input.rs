@@ -1,9 +1,12 @@
 use crate::buttons::BUTTONS;
+use std::collections::VecDeque;
 use winit::keyboard::KeyCode;
 
 pub enum Event {
     Restart,
-    CreateBox(f32, f32),
+    GrabOrCreateBox(f32, f32),
+    Drag(f32, f32),
+    EndDrag,
     RunScene(usize),
     ChangeToNextScene,
     ChangeToPrevScene,
@@ -12,7 +15,8 @@ pub enum Event {
 pub struct InputState {
     cursor: CursorState,
     keyboard: KeyBoardState,
-    events: Vec<Event>,
+    // Oldest first, so a press and release in one frame end the drag
+    events: VecDeque<Event>,
 }
 
 impl Default for InputState {
@@ -20,7 +24,7 @@ impl Default for InputState {
         Self {
             cursor: Default::default(),
             keyboard: Default::default(),
-            events: Vec::new(),
+            events: VecDeque::new(),
         }
     }
 }
@@ -33,13 +37,13 @@ impl InputState {
                 if b.in_button(self.cursor.x, self.cursor.y) {
                     match name {
                         &"Prev" => {
-                            self.events.push(Event::ChangeToPrevScene);
+                            self.events.push_back(Event::ChangeToPrevScene);
                         }
                         &"Restart" => {
-                            self.events.push(Event::Restart);
+                            self.events.push_back(Event::Restart);
                         }
                         &"Next" => {
-                            self.events.push(Event::ChangeToNextScene);
+                            self.events.push_back(Event::ChangeToNextScene);
                         }
                         _ => {}
                     }
@@ -47,33 +51,39 @@ impl InputState {
                 }
             }
             self.events
-                .push(Event::CreateBox(self.cursor.x, self.cursor.y));
+                .push_back(Event::GrabOrCreateBox(self.cursor.x, self.cursor.y));
+        } else {
+            self.events.push_back(Event::EndDrag);
         }
     }
 
     pub fn update_cursor_pos(&mut self, x: f32, y: f32) {
         self.cursor.set_pos(x, y);
+        if self.cursor.button_pressed {
+            self.events.push_back(Event::Drag(x, y));
+        }
     }
 
     pub fn update_keyboard(&mut self, pressed: bool, key: KeyCode) {
         self.keyboard.update(pressed, key);
         if self.keyboard.space_pressed {
-            self.events.push(Event::Restart);
+            self.events.push_back(Event::Restart);
         }
         if self.keyboard.digit_pressed {
-            self.events.push(Event::RunScene(self.keyboard.digit_num));
+            self.events
+                .push_back(Event::RunScene(self.keyboard.digit_num));
         }
         if self.keyboard.letter_pressed {
             match self.keyboard.letter {
-                KeyLetters::N => self.events.push(Event::ChangeToNextScene),
-                KeyLetters::P => self.events.push(Event::ChangeToPrevScene),
+                KeyLetters::N => self.events.push_back(Event::ChangeToNextScene),
+                KeyLetters::P => self.events.push_back(Event::ChangeToPrevScene),
                 _ => {}
             }
         }
     }
 
     pub fn pop_event(&mut self) -> Option<Event> {
-        self.events.pop()
+        self.events.pop_front()
     }
 }
 
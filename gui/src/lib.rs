@@ -145,7 +145,7 @@ impl ApplicationHandler for App {
                 let joint_lines = physics_state.get_joint_lines();
 
                 let controls_text = "Controls: 1-9 scenes; Space: restart; P, N - prev, next scene;
-               Click - add box";
+               Click - add box, drag box";
                 if cfg!(debug_assertions) {
                     render_state.text = format!(
                         "{}\nfps: {:.3}\n{}\n{}",
@@ -241,8 +241,16 @@ fn input_events_to_physics(input_state: &mut InputState, physics_state: &mut Phy
             Restart => {
                 physics_state.restart();
             }
-            CreateBox(x, y) => {
-                physics_state.add_rectangle(x, y);
+            GrabOrCreateBox(x, y) => {
+                if !physics_state.start_drag(x, y) {
+                    physics_state.add_rectangle(x, y);
+                }
+            }
+            Drag(x, y) => {
+                physics_state.drag(x, y);
+            }
+            EndDrag => {
+                physics_state.end_drag();
             }
             RunScene(scene) => {
                 *physics_state = PhysicsState::new(scene);
@@ -36,7 +36,7 @@ async fn main() {
             ui.label(None, &format!("{}", physics_state));
             ui.label(None, &format!("Controls: 1-9 scenes; Space: restart"));
             ui.label(None, &format!("          P, N - prev, next scene"));
-            ui.label(None, &format!("          Click - add box"));
+            ui.label(None, &format!("          Click - add box, drag box"));
         });
         draw_rectangle_lines(
             size_params.offset_x,
@@ -155,9 +155,18 @@ fn update_physics_by_input(
         };
         if !pressed {
             let (x, y) = transform_coords_back(mouse_x, mouse_y, &size_params);
-            physics_state.add_rectangle(x, y);
+            if !physics_state.start_drag(x, y) {
+                physics_state.add_rectangle(x, y);
+            }
         }
     }
+    if is_mouse_button_down(MouseButton::Left) {
+        let (x, y) = transform_coords_back(mouse_x, mouse_y, &size_params);
+        physics_state.drag(x, y);
+    }
+    if is_mouse_button_released(MouseButton::Left) {
+        physics_state.end_drag();
+    }
 }
 
 const GRAY_BACKGROUND: Color = Color {
@@ -1,4 +1,4 @@
-use crate::PhysicsState;
+use crate::{PhysicsState, to_f32, to_real};
 
 #[cfg(feature = "random")]
 use rand::Rng;
@@ -45,24 +45,23 @@ fn init_two_boxes_scene(state: &mut PhysicsState) {
 fn init_simple_pendulum(state: &mut PhysicsState) {
     let center_y = 0.4;
     let r = 0.8;
-    let angle = std::f32::consts::PI + std::f32::consts::PI / 4.0;
-    let x = r * f32::cos(angle);
-    let y = center_y + r * f32::sin(angle);
+    let angle = to_real(std::f32::consts::PI + std::f32::consts::PI / 4.0);
+    let x = r * to_f32(box2d::cos(angle));
+    let y = center_y + r * to_f32(box2d::sin(angle));
 
-    let body1 = state.add_unmovable_body(SCREEN_WIDTH, FLOOR_H, 0.0, FLOOR_Y_CENTER);
-    let body2 = state.add_body(0.2, 0.2, 200.0, x, y);
+    let ground = state.world.ground();
+    let body = state.add_body(0.2, 0.2, 200.0, x, y);
 
-    let _anchor_ref = state.add_unmovable_body(0.05, 0.05, 0.0, center_y);
-    state.add_joint(body1, body2, 0.0, center_y);
+    state.add_joint(ground, body, 0.0, center_y);
 }
 
 fn init_varying_friction_coefficients(state: &mut PhysicsState) {
     let slide = state.add_unmovable_body(1.1, 0.04, -0.2, FLOOR_Y + 1.25);
-    slide.borrow_mut().rotation = -0.25;
+    slide.borrow_mut().set_rotation(to_real(-0.25));
     let slide = state.add_unmovable_body(1.1, 0.04, 0.05, FLOOR_Y + 0.75);
-    slide.borrow_mut().rotation = 0.25;
+    slide.borrow_mut().set_rotation(to_real(0.25));
     let slide = state.add_unmovable_body(1.1, 0.04, -0.25, FLOOR_Y + 0.25);
-    slide.borrow_mut().rotation = -0.25;
+    slide.borrow_mut().set_rotation(to_real(-0.25));
 
     let _wall = state.add_unmovable_body(0.04, 0.3, 0.55, FLOOR_Y + 1.05);
     let _wall = state.add_unmovable_body(0.04, 0.3, -0.75, FLOOR_Y + 0.55);
@@ -70,7 +69,7 @@ fn init_varying_friction_coefficients(state: &mut PhysicsState) {
     let friction = [0.75, 0.5, 0.35, 0.1, 0.0];
     for i in 0..5 {
         let body = state.add_body(0.1, 0.1, 150.0, -0.65 + i as f32 * 0.15, FLOOR_Y + 1.65);
-        body.borrow_mut().friction = friction[i];
+        body.borrow_mut().friction = to_real(friction[i]);
     }
 
     let _floor = state.add_unmovable_body(SCREEN_WIDTH, FLOOR_H, 0.0, FLOOR_Y_CENTER);
@@ -81,6 +80,7 @@ fn init_vertical_stack_scene(state: &mut PhysicsState) {
     const UNSTABLE_COEF: f32 = 1.0 / 4.0;
     const STABLE_COEF: f32 = 1.0 / 6.0;
     const MASS: f32 = 200.0;
+    state.world.set_block_solver(true);
     for i in 0..6 {
         let offset = if i % 2 == 0 {
             -W * UNSTABLE_COEF
@@ -134,7 +134,11 @@ fn init_teeter(state: &mut PhysicsState) {
 
     let teeter = state.add_body(teeter_w, 0.05, 50.0, 0.0, anchor_y);
     let floor = state.add_unmovable_body(SCREEN_WIDTH, FLOOR_H, 0.0, FLOOR_Y_CENTER);
-    state.add_joint(teeter, floor, 0.0, anchor_y);
+    let joint = state.add_revolute_joint(floor, teeter, 0.0, anchor_y);
+    let mut joint = joint.borrow_mut();
+    // Stop the teeter right before its ends touch the floor
+    joint.enable_limit = true;
+    joint.set_limits(to_real(-0.15), to_real(0.15));
 
     let _big = state.add_body(0.2, 0.2, 200.0, teeter_w / 2.0 - 0.1, FLOOR_Y + 0.8);
     let _small1 = state.add_body(0.1, 0.1, 100.0, -(teeter_w / 2.0 - 0.04), FLOOR_Y + 0.3);
@@ -155,6 +159,8 @@ fn init_bridge(state: &mut PhysicsState) {
 
     // Tuning
     let (softness, bias_factor) = calc_softness_bias(2.0, 0.7, mass);
+    // Survives its own sag, snaps under a couple of boxes
+    let break_force = Some(to_real(8.0e5));
 
     let mut prev_body = floor.clone();
     for i in 0..count {
@@ -163,8 +169,9 @@ fn init_bridge(state: &mut PhysicsState) {
         let pos_x = pos_x - W / 2.0;
         let joint = state.add_joint(prev_body, cur_body.clone(), pos_x, pos_y);
         let mut joint = joint.borrow_mut();
-        joint.softness = softness;
-        joint.bias_factor = bias_factor;
+        joint.softness = to_real(softness);
+        joint.bias_factor = to_real(bias_factor);
+        joint.break_force = break_force;
         prev_body = cur_body;
     }
     let joint = state.add_joint(
@@ -174,8 +181,9 @@ fn init_bridge(state: &mut PhysicsState) {
         pos_y,
     );
     let mut joint = joint.borrow_mut();
-    joint.softness = softness;
-    joint.bias_factor = bias_factor;
+    joint.softness = to_real(softness);
+    joint.bias_factor = to_real(bias_factor);
+    joint.break_force = break_force;
 }
 
 fn init_multi_pendulum(state: &mut PhysicsState) {
@@ -197,8 +205,8 @@ fn init_multi_pendulum(state: &mut PhysicsState) {
         let pos_x = pos_x - W / 2.0;
         let joint = state.add_joint(prev_body.clone(), cur_body.clone(), pos_x, pos_y);
         let mut joint = joint.borrow_mut();
-        joint.softness = softness;
-        joint.bias_factor = bias_factor;
+        joint.softness = to_real(softness);
+        joint.bias_factor = to_real(bias_factor);
         prev_body = cur_body;
     }
 }
@@ -4,29 +4,50 @@ pub mod primitives;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use box2d::{Body, Joint, UNMOVABLE_MASS, Vec2, World};
+use box2d::{
+    Body, FixedStepper, Joint, MouseJoint, Real, RevoluteJoint, Transform, UNMOVABLE_MASS, Vec2,
+    World, real,
+};
 use primitives::{Line, Point, Rectangle};
 
 pub struct PhysicsState {
     world: World,
+    stepper: FixedStepper,
     current_scene: usize,
+    mouse_joint: Option<Rc<RefCell<MouseJoint>>>,
 }
 
-const SCALE_MULT: f32 = 100.0;
-const GRAVITY: f32 = -500.0;
+const SCALE_MULT: Real = real(100.0);
+const GRAVITY: Real = real(-500.0);
+// Max drag force in units of the dragged body weight
+const DRAG_FORCE_MULT: Real = real(50.0);
+const TIME_STEP: Real = real(1.0 / 60.0);
+// Steps per frame before the simulation gives up catching up
+const MAX_STEPS: u32 = 5;
 
 impl PhysicsState {
     fn add_unmovable_body(&mut self, w: f32, h: f32, pos_x: f32, pos_y: f32) -> Rc<RefCell<Body>> {
-        self.add_body(w, h, UNMOVABLE_MASS, pos_x, pos_y)
+        self.insert_body(w, h, UNMOVABLE_MASS, pos_x, pos_y)
     }
 
     fn add_body(&mut self, w: f32, h: f32, mass: f32, pos_x: f32, pos_y: f32) -> Rc<RefCell<Body>> {
+        self.insert_body(w, h, to_real(mass), pos_x, pos_y)
+    }
+
+    fn insert_body(
+        &mut self,
+        w: f32,
+        h: f32,
+        mass: Real,
+        pos_x: f32,
+        pos_y: f32,
+    ) -> Rc<RefCell<Body>> {
         let body = Rc::new(RefCell::new(Body::new(
-            w * SCALE_MULT,
-            h * SCALE_MULT,
+            to_real(w) * SCALE_MULT,
+            to_real(h) * SCALE_MULT,
             mass,
-            pos_x * SCALE_MULT,
-            pos_y * SCALE_MULT,
+            to_real(pos_x) * SCALE_MULT,
+            to_real(pos_y) * SCALE_MULT,
         )));
         self.world.add_body(body.clone());
         body
@@ -39,44 +60,55 @@ impl PhysicsState {
         anchor_x: f32,
         anchor_y: f32,
     ) -> Rc<RefCell<Joint>> {
-        let joint = self.world.add_joint(
-            body1,
-            body2,
-            Vec2::new(anchor_x * SCALE_MULT, anchor_y * SCALE_MULT),
-        );
+        let joint = self
+            .world
+            .add_joint(body1, body2, to_world(anchor_x, anchor_y));
         joint
     }
 
+    fn add_revolute_joint(
+        &mut self,
+        body1: Rc<RefCell<Body>>,
+        body2: Rc<RefCell<Body>>,
+        anchor_x: f32,
+        anchor_y: f32,
+    ) -> Rc<RefCell<RevoluteJoint>> {
+        self.world
+            .add_revolute_joint(body1, body2, to_world(anchor_x, anchor_y))
+    }
+
     pub fn new(demo_scene: usize) -> Self {
-        let gravity = Vec2::new(0.0, GRAVITY);
+        let gravity = Vec2::new(real(0.0), GRAVITY);
         let iterations = 10;
         let mut state = PhysicsState {
             world: World::new(gravity, iterations),
+            stepper: FixedStepper::new(TIME_STEP, MAX_STEPS),
             current_scene: demo_scene,
+            mouse_joint: None,
         };
         demo_scenes::init_scene(demo_scene, &mut state);
         state
     }
 
+    // Advances by the frame time in fixed steps
     pub fn step(&mut self, dt: f32) {
-        self.world.step(dt);
+        self.stepper.advance(&mut self.world, to_real(dt));
     }
 
     pub fn get_rectangles(&self) -> Vec<Rectangle> {
         self.world
             .get_bodies()
             .iter()
-            .map(|body| {
+            .enumerate()
+            .map(|(i, body)| {
                 let body = body.borrow();
-                let center = &body.position;
+                let Transform { position, rotation } =
+                    self.stepper.interpolated(i).unwrap_or(body.transform());
                 Rectangle {
-                    center: Point {
-                        x: center.x / SCALE_MULT,
-                        y: center.y / SCALE_MULT,
-                    },
-                    width: body.width.x / SCALE_MULT,
-                    height: body.width.y / SCALE_MULT,
-                    rotation: body.rotation,
+                    center: to_point(position),
+                    width: to_f32(body.width.x / SCALE_MULT),
+                    height: to_f32(body.width.y / SCALE_MULT),
+                    rotation: to_f32(rotation.angle()),
                 }
             })
             .collect()
@@ -86,10 +118,7 @@ impl PhysicsState {
         self.world
             .get_collide_points()
             .iter()
-            .map(|p| Point {
-                x: p.x / SCALE_MULT,
-                y: p.y / SCALE_MULT,
-            })
+            .map(|p| to_point(*p))
             .collect()
     }
 
@@ -98,14 +127,8 @@ impl PhysicsState {
             .get_joint_lines()
             .iter()
             .map(|(start, end)| Line {
-                p1: Point {
-                    x: start.x / SCALE_MULT,
-                    y: start.y / SCALE_MULT,
-                },
-                p2: Point {
-                    x: end.x / SCALE_MULT,
-                    y: end.y / SCALE_MULT,
-                },
+                p1: to_point(*start),
+                p2: to_point(*end),
             })
             .collect()
     }
@@ -114,6 +137,42 @@ impl PhysicsState {
         self.add_body(0.15, 0.15, 200.0, x, y);
     }
 
+    // Grab the movable body under the point, returns false if there is none
+    pub fn start_drag(&mut self, x: f32, y: f32) -> bool {
+        self.end_drag();
+        let point = to_world(x, y);
+        let Some(body) = self
+            .world
+            .get_bodies()
+            .iter()
+            .find(|body| {
+                let body = body.borrow();
+                body.inv_mass > 0.0 && body.contains_point(point)
+            })
+            .cloned()
+        else {
+            return false;
+        };
+
+        let mass = body.borrow().mass();
+        let joint = self.world.add_mouse_joint(body, point);
+        joint.borrow_mut().max_force = DRAG_FORCE_MULT * mass * GRAVITY.abs();
+        self.mouse_joint = Some(joint);
+        true
+    }
+
+    pub fn drag(&mut self, x: f32, y: f32) {
+        if let Some(joint) = &self.mouse_joint {
+            joint.borrow_mut().set_target(to_world(x, y));
+        }
+    }
+
+    pub fn end_drag(&mut self) {
+        if let Some(joint) = self.mouse_joint.take() {
+            self.world.remove_joint(&joint);
+        }
+    }
+
     pub fn restart(&mut self) {
         let scene = self.current_scene;
         *self = PhysicsState::new(scene);
@@ -134,6 +193,40 @@ impl PhysicsState {
     }
 }
 
+// Scenes and the GUI work in f32 whatever Real the engine runs on
+#[cfg(not(feature = "fixed-point"))]
+pub fn to_real(v: f32) -> Real {
+    v as Real
+}
+
+#[cfg(feature = "fixed-point")]
+pub fn to_real(v: f32) -> Real {
+    Real::from_f32(v)
+}
+
+// A no-op unless the engine runs in f64
+#[cfg(not(feature = "fixed-point"))]
+#[allow(clippy::unnecessary_cast)]
+pub fn to_f32(v: Real) -> f32 {
+    v as f32
+}
+
+#[cfg(feature = "fixed-point")]
+pub fn to_f32(v: Real) -> f32 {
+    v.to_f32()
+}
+
+fn to_world(x: f32, y: f32) -> Vec2 {
+    Vec2::new(to_real(x) * SCALE_MULT, to_real(y) * SCALE_MULT)
+}
+
+fn to_point(p: Vec2) -> Point {
+    Point {
+        x: to_f32(p.x / SCALE_MULT),
+        y: to_f32(p.y / SCALE_MULT),
+    }
+}
+
 impl std::fmt::Display for PhysicsState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -145,3 +238,959 @@ impl std::fmt::Display for PhysicsState {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use box2d::{Constraint, PositionCorrection, SolverMode};
+
+    const STEPS: usize = 120;
+
+    // FNV-1a over the bits of every body state, stable across Rust releases
+    fn hash_world(world: &World) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for body in world.get_bodies() {
+            let body = body.borrow();
+            let values = [
+                body.position.x,
+                body.position.y,
+                body.rotation(),
+                body.velocity.x,
+                body.velocity.y,
+                body.angular_velocity,
+            ];
+            for byte in values.iter().flat_map(|v| v.to_bits().to_le_bytes()) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    fn run_scene(scene: usize) -> u64 {
+        let mut state = PhysicsState::new(scene);
+        for _ in 0..STEPS {
+            state.world.step(TIME_STEP);
+        }
+        hash_world(&state.world)
+    }
+
+    // Smoke test only: both runs share the binary and the platform, so this
+    // catches state leaking between worlds or address dependent ordering, not
+    // cross-platform drift. That is what the recorded hashes below check.
+    #[test]
+    #[cfg(not(feature = "random"))]
+    fn scenes_repeat_exactly() {
+        for scene in 0..demo_scenes::get_scene_amount() {
+            assert_eq!(run_scene(scene), run_scene(scene), "scene {scene}");
+        }
+    }
+
+    // Recorded hashes must match on every platform with portable math
+    #[test]
+    #[cfg(all(
+        feature = "deterministic",
+        not(feature = "random"),
+        not(feature = "f64"),
+        not(feature = "fixed-point")
+    ))]
+    fn scenes_match_recorded_hashes() {
+        const EXPECTED: &[u64] = &[
+            0x4910240b4b9047e0,
+            0x25f5532f488930c0,
+            0xffd9c3889c841fe7,
+            0xe4978bb91f29bdc8,
+            0x9f7abc703e578ffc,
+            0x3b1cb125c421238c,
+            0xfd085696532a8a9a,
+            0x66cc2db6541a05cb,
+            0x3f5dc1e33c44af1d,
+        ];
+        let hashes: Vec<u64> = (0..demo_scenes::get_scene_amount())
+            .map(run_scene)
+            .collect();
+        assert_eq!(hashes, EXPECTED);
+    }
+
+    // Fixed point is integer arithmetic, reproducible without extra features
+    #[test]
+    #[cfg(all(feature = "fixed-point", not(feature = "random")))]
+    fn fixed_point_scenes_match_recorded_hashes() {
+        const EXPECTED: &[u64] = &[
+            0xc2a86665ac7b1211,
+            0x28647404d98e8f14,
+            0x0a815b22791972f7,
+            0xec7775139bc960a2,
+            0xe9943e1d26050233,
+            0x462c321b85c29024,
+            0x8ee3853f7a2d9fa4,
+            0x7f8a5da637f0354f,
+            0xcdf944e71c2b19ec,
+        ];
+        let hashes: Vec<u64> = (0..demo_scenes::get_scene_amount())
+            .map(run_scene)
+            .collect();
+        assert_eq!(hashes, EXPECTED);
+    }
+
+    // Zero-gravity world with one unit box at (x, 0)
+    fn single_body_world(x: Real) -> (World, Rc<RefCell<Body>>) {
+        let mut world = World::new(Vec2::ZERO, 10);
+        let body = Rc::new(RefCell::new(Body::new(
+            real(1.0),
+            real(1.0),
+            real(1.0),
+            x,
+            real(0.0),
+        )));
+        world.add_body(body.clone());
+        (world, body)
+    }
+
+    // Around 1e5 f32 positions are 1/128 apart, so the 1/600 a slow body
+    // moves per step rounds away and it never leaves its spot. f64 and
+    // fixed point keep the motion.
+    #[test]
+    fn slow_body_far_from_origin() {
+        let (mut world, body) = single_body_world(real(100000.0));
+        body.borrow_mut().velocity.x = real(0.1);
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+        }
+        let travelled = body.borrow().position.x - real(100000.0);
+        if cfg!(any(feature = "f64", feature = "fixed-point")) {
+            assert!((travelled - real(0.2)).abs() < real(1e-6), "{travelled}");
+        } else {
+            assert_eq!(travelled, real(0.0));
+        }
+    }
+
+    #[test]
+    fn distance_joint_spring_oscillates() {
+        let (mut world, body) = single_body_world(real(2.0));
+        let joint = world.add_distance_joint(
+            world.ground(),
+            body.clone(),
+            Vec2::ZERO,
+            Vec2::new(real(2.0), real(0.0)),
+        );
+        joint.borrow_mut().stiffness = real(100.0);
+        joint.borrow_mut().damping = real(0.5);
+        body.borrow_mut().position.x = real(3.0);
+
+        // About three periods, each crossing the rest length twice
+        let mut crossings = 0;
+        let mut stretched = true;
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+            let now_stretched = body.borrow().position.x > 2.0;
+            if now_stretched != stretched {
+                crossings += 1;
+                stretched = now_stretched;
+            }
+        }
+        assert!(crossings >= 4, "{crossings} crossings");
+    }
+
+    #[test]
+    fn revolute_limit_past_half_turn() {
+        let (mut world, body) = single_body_world(real(0.0));
+        let joint = world.add_revolute_joint(world.ground(), body, Vec2::ZERO);
+        {
+            let mut joint = joint.borrow_mut();
+            joint.set_limits(real(0.0), real(1.5) * box2d::PI);
+            joint.enable_limit = true;
+            joint.enable_motor = true;
+            joint.motor_speed = real(4.0);
+            joint.max_motor_torque = real(10.0);
+        }
+
+        // The motor alone would turn it by 8 rad in the two seconds
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+        }
+        let angle = joint.borrow().angle();
+        assert!(
+            (angle - real(1.5) * box2d::PI).abs() < real(0.05),
+            "{angle}"
+        );
+    }
+
+    #[test]
+    fn gear_joint_breaks_with_coupled_joint() {
+        let (mut world, body1) = single_body_world(real(0.0));
+        let body2 = Rc::new(RefCell::new(Body::new(
+            real(1.0),
+            real(1.0),
+            real(1.0),
+            real(3.0),
+            real(0.0),
+        )));
+        world.add_body(body2.clone());
+        let revolute1 = world.add_revolute_joint(world.ground(), body1, Vec2::ZERO);
+        let revolute2 =
+            world.add_revolute_joint(world.ground(), body2, Vec2::new(real(3.0), real(0.0)));
+        world.add_gear_joint(revolute1.clone().into(), revolute2.into(), real(1.0));
+
+        world.remove_joint(&revolute1);
+        assert_eq!(world.take_broken_joints().len(), 1);
+        assert_eq!(world.get_joint_lines().len(), 2);
+    }
+
+    // Largest anchor separation of a swinging five link chain over two seconds
+    fn chain_drift(position_correction: PositionCorrection, solver_mode: SolverMode) -> Real {
+        let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), 10);
+        world.set_position_correction(position_correction);
+        world.set_solver_mode(solver_mode);
+        let mut joints = Vec::new();
+        let mut previous = world.ground();
+        for i in 0..5 {
+            let x = real(i as f64 + 0.5);
+            let link = Rc::new(RefCell::new(Body::new(
+                real(1.0),
+                real(0.25),
+                real(1.0),
+                x,
+                real(0.0),
+            )));
+            world.add_body(link.clone());
+            let anchor = Vec2::new(x - 0.5, real(0.0));
+            joints.push(world.add_joint(previous, link.clone(), anchor));
+            previous = link;
+        }
+
+        let mut drift = real(0.0);
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+            for joint in &joints {
+                let lines = joint.borrow().get_joint_lines();
+                drift = drift.max(lines[0].1.distance(lines[1].1));
+            }
+        }
+        drift
+    }
+
+    #[test]
+    fn joint_drift_stays_small() {
+        let soft_step = SolverMode::SoftStep {
+            substeps: 4,
+            contact_hertz: real(30.0),
+            contact_damping_ratio: real(10.0),
+            joint_hertz: real(60.0),
+            joint_damping_ratio: real(2.0),
+        };
+        // NGS and the substepped soft step should hold the default slop
+        for (position_correction, solver_mode, max_drift) in [
+            (PositionCorrection::Baumgarte, SolverMode::Iterations, 0.1),
+            (
+                PositionCorrection::NonlinearGaussSeidel,
+                SolverMode::Iterations,
+                0.01,
+            ),
+            (PositionCorrection::Baumgarte, soft_step, 0.01),
+        ] {
+            let drift = chain_drift(position_correction, solver_mode);
+            assert!(
+                drift < max_drift,
+                "{position_correction:?} {solver_mode:?}: {drift}"
+            );
+        }
+    }
+
+    // A rotation locked box and an x locked box tied by a distance joint,
+    // dropped on a slope
+    fn locked_bodies_on_slope(solver_mode: SolverMode, position_correction: PositionCorrection) {
+        let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), 10);
+        world.set_solver_mode(solver_mode);
+        world.set_position_correction(position_correction);
+        let mut slope = Body::new(real(20.0), real(1.0), UNMOVABLE_MASS, real(0.0), real(0.0));
+        slope.set_rotation(real(0.3));
+        world.add_body(Rc::new(RefCell::new(slope)));
+
+        let mut body = Body::new(real(1.0), real(1.0), real(1.0), real(-2.0), real(3.0));
+        body.fixed_rotation = true;
+        let no_rotation = Rc::new(RefCell::new(body));
+        world.add_body(no_rotation.clone());
+        let mut body = Body::new(real(1.0), real(1.0), real(1.0), real(2.0), real(3.0));
+        body.lock_translation_x = true;
+        let fixed_x = Rc::new(RefCell::new(body));
+        world.add_body(fixed_x.clone());
+        world.add_distance_joint(
+            no_rotation.clone(),
+            fixed_x.clone(),
+            Vec2::new(real(-2.0), real(3.0)),
+            Vec2::new(real(2.0), real(3.0)),
+        );
+
+        let mut touched = false;
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+            touched |= !world.get_collide_points().is_empty();
+            let no_rotation = no_rotation.borrow();
+            assert_eq!(no_rotation.rotation(), real(0.0), "{solver_mode:?}");
+            assert_eq!(no_rotation.angular_velocity, real(0.0), "{solver_mode:?}");
+            let fixed_x = fixed_x.borrow();
+            assert_eq!(fixed_x.position.x, real(2.0), "{solver_mode:?}");
+            assert_eq!(fixed_x.velocity.x, real(0.0), "{solver_mode:?}");
+        }
+        assert!(touched, "{solver_mode:?} {position_correction:?}");
+        // Free degrees of freedom still move
+        assert!(no_rotation.borrow().position.y < real(2.0));
+        assert!(fixed_x.borrow().rotation() != real(0.0));
+    }
+
+    #[test]
+    fn locked_dofs_stay_fixed() {
+        for position_correction in [
+            PositionCorrection::Baumgarte,
+            PositionCorrection::SplitImpulse,
+            PositionCorrection::NonlinearGaussSeidel,
+        ] {
+            locked_bodies_on_slope(SolverMode::Iterations, position_correction);
+        }
+        let soft_step = SolverMode::SoftStep {
+            substeps: 4,
+            contact_hertz: real(30.0),
+            contact_damping_ratio: real(10.0),
+            joint_hertz: real(60.0),
+            joint_damping_ratio: real(2.0),
+        };
+        locked_bodies_on_slope(soft_step, PositionCorrection::Baumgarte);
+    }
+
+    #[test]
+    fn off_center_force_turns_body() {
+        let (mut world, body) = single_body_world(real(0.0));
+        // Unit box of mass 1: inverse inertia 6
+        body.borrow_mut().apply_force_at_point(
+            Vec2::new(real(0.0), real(2.0)),
+            Vec2::new(real(0.5), real(0.0)),
+        );
+        assert_eq!(body.borrow().torque, real(1.0));
+
+        world.step(TIME_STEP);
+        let body = body.borrow();
+        assert!((body.angular_velocity - real(6.0) * TIME_STEP).abs() < real(1e-4));
+        assert!((body.velocity.y - real(2.0) * TIME_STEP).abs() < real(1e-4));
+        // Forces are cleared after the step
+        assert_eq!(body.torque, real(0.0));
+    }
+
+    #[test]
+    fn impulses_respect_locks() {
+        let mut body = Body::new(real(1.0), real(1.0), real(1.0), real(0.0), real(0.0));
+        body.lock_translation_x = true;
+        body.fixed_rotation = true;
+        body.apply_linear_impulse(
+            Vec2::new(real(1.0), real(1.0)),
+            Vec2::new(real(0.5), real(0.5)),
+        );
+        body.apply_angular_impulse(real(1.0));
+        assert_eq!(body.velocity, Vec2::new(real(0.0), real(1.0)));
+        assert_eq!(body.angular_velocity, real(0.0));
+
+        body.lock_translation_x = false;
+        body.fixed_rotation = false;
+        body.apply_linear_impulse(
+            Vec2::new(real(1.0), real(0.0)),
+            Vec2::new(real(0.0), real(0.5)),
+        );
+        assert_eq!(body.velocity, Vec2::new(real(1.0), real(1.0)));
+        // r x p = (0, 0.5) x (1, 0) = -0.5, times the inverse inertia 6
+        assert!((body.angular_velocity + real(3.0)).abs() < real(1e-4));
+    }
+
+    // Unit box sliding along x from the origin, pinned to the ground
+    fn prismatic_world() -> (World, Rc<RefCell<Body>>, Rc<RefCell<box2d::PrismaticJoint>>) {
+        let (mut world, body) = single_body_world(real(0.0));
+        let joint = world.add_prismatic_joint(
+            world.ground(),
+            body.clone(),
+            Vec2::ZERO,
+            Vec2::new(real(2.0), real(0.0)),
+        );
+        (world, body, joint)
+    }
+
+    #[test]
+    fn prismatic_keeps_body_on_axis() {
+        let (mut world, body, joint) = prismatic_world();
+        body.borrow_mut().velocity = Vec2::new(real(1.0), real(1.0));
+        body.borrow_mut().angular_velocity = real(1.0);
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+        }
+        let body = body.borrow();
+        assert!(body.position.y.abs() < real(0.01), "{:?}", body.position);
+        assert!(body.rotation().abs() < real(0.01), "{}", body.rotation());
+        // The velocity along the axis is kept
+        let translation = joint.borrow().translation();
+        assert!(
+            (translation - real(2.0)).abs() < real(0.05),
+            "{translation}"
+        );
+    }
+
+    #[test]
+    fn prismatic_limit_stops_body() {
+        let (mut world, body, joint) = prismatic_world();
+        {
+            let mut joint = joint.borrow_mut();
+            joint.set_limits(real(0.5), real(-0.25));
+            joint.enable_limit = true;
+        }
+        body.borrow_mut().velocity = Vec2::new(real(3.0), real(0.0));
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+        }
+        let translation = joint.borrow().translation();
+        assert!(
+            (translation - real(0.5)).abs() < real(0.02),
+            "{translation}"
+        );
+
+        body.borrow_mut().velocity = Vec2::new(real(-3.0), real(0.0));
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+        }
+        let translation = joint.borrow().translation();
+        assert!(
+            (translation + real(0.25)).abs() < real(0.02),
+            "{translation}"
+        );
+    }
+
+    #[test]
+    fn prismatic_motor_drives_body() {
+        let (mut world, body, joint) = prismatic_world();
+        {
+            let mut joint = joint.borrow_mut();
+            joint.enable_motor = true;
+            joint.motor_speed = real(-1.5);
+            joint.max_motor_force = real(100.0);
+        }
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+        }
+        let velocity = body.borrow().velocity;
+        assert_close(velocity, Vec2::new(real(-1.5), real(0.0)));
+        let translation = joint.borrow().translation();
+        assert!(
+            (translation + real(3.0)).abs() < real(0.05),
+            "{translation}"
+        );
+
+        // A weak motor only accelerates the body: 1 N on 1 kg
+        joint.borrow_mut().motor_speed = real(10.0);
+        joint.borrow_mut().max_motor_force = real(1.0);
+        world.step(TIME_STEP);
+        let speed = body.borrow().velocity.x;
+        assert!(
+            (speed - real(-1.5 + 1.0 / 60.0)).abs() < real(1e-3),
+            "{speed}"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "joint axis must be non-zero")]
+    fn prismatic_rejects_zero_axis() {
+        let (mut world, body) = single_body_world(real(0.0));
+        world.add_prismatic_joint(world.ground(), body, Vec2::ZERO, Vec2::ZERO);
+    }
+
+    #[test]
+    fn wheel_spring_holds_sag() {
+        let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), 10);
+        let body = Rc::new(RefCell::new(Body::new(
+            real(1.0),
+            real(1.0),
+            real(1.0),
+            real(0.0),
+            real(0.0),
+        )));
+        world.add_body(body.clone());
+        let joint = world.add_wheel_joint(
+            world.ground(),
+            body.clone(),
+            Vec2::ZERO,
+            Vec2::new(real(0.0), real(1.0)),
+        );
+        // Released from rest the spring overshoots the static sag, then settles
+        let omega = real(2.0) * box2d::PI * joint.borrow().frequency_hz;
+        let sag = real(-10.0) / (omega * omega);
+        let mut lowest = real(0.0);
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+            lowest = lowest.min(joint.borrow().translation());
+        }
+        assert!(lowest < real(1.02) * sag, "{lowest} {sag}");
+        let translation = joint.borrow().translation();
+        assert!(
+            (translation - sag).abs() < real(0.005),
+            "{translation} {sag}"
+        );
+        assert!(body.borrow().position.x.abs() < real(1e-3));
+    }
+
+    #[test]
+    fn wheel_motor_spins_wheel() {
+        let (mut world, body) = single_body_world(real(0.0));
+        let joint = world.add_wheel_joint(
+            world.ground(),
+            body.clone(),
+            Vec2::ZERO,
+            Vec2::new(real(0.0), real(1.0)),
+        );
+        {
+            let mut joint = joint.borrow_mut();
+            joint.enable_motor = true;
+            joint.motor_speed = real(5.0);
+            joint.max_motor_torque = real(100.0);
+        }
+        for _ in 0..10 {
+            world.step(TIME_STEP);
+        }
+        let speed = joint.borrow().angular_speed();
+        assert!((speed - real(5.0)).abs() < real(1e-3), "{speed}");
+
+        // Torque limited: 1 N m on the unit box, inverse inertia 6
+        joint.borrow_mut().motor_speed = real(-5.0);
+        joint.borrow_mut().max_motor_torque = real(1.0);
+        world.step(TIME_STEP);
+        let speed = joint.borrow().angular_speed();
+        assert!(
+            (speed - real(5.0 - 6.0 / 60.0)).abs() < real(1e-3),
+            "{speed}"
+        );
+        assert!(body.borrow().position.length() < real(1e-3));
+    }
+
+    #[test]
+    #[should_panic(expected = "joint axis must be non-zero")]
+    fn wheel_rejects_zero_axis() {
+        let (mut world, body) = single_body_world(real(0.0));
+        world.add_wheel_joint(world.ground(), body, Vec2::ZERO, Vec2::ZERO);
+    }
+
+    // Boxes at x = -2 and 2, bodies 0 and 1 of the world, hanging from
+    // pulleys 4.5 above their centers
+    fn pulley_world(
+        mass1: Real,
+        mass2: Real,
+        ratio: Real,
+    ) -> (World, Rc<RefCell<box2d::PulleyJoint>>) {
+        let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), 10);
+        let [body1, body2] = [(-2.0, mass1), (2.0, mass2)].map(|(x, mass)| {
+            let body = Rc::new(RefCell::new(Body::new(
+                real(1.0),
+                real(1.0),
+                mass,
+                real(x),
+                real(0.0),
+            )));
+            world.add_body(body.clone());
+            body
+        });
+        let joint = world.add_pulley_joint(
+            body1,
+            body2,
+            [
+                Vec2::new(real(-2.0), real(5.0)),
+                Vec2::new(real(2.0), real(5.0)),
+            ],
+            [
+                Vec2::new(real(-2.0), real(0.5)),
+                Vec2::new(real(2.0), real(0.5)),
+            ],
+            ratio,
+        );
+        (world, joint)
+    }
+
+    #[test]
+    fn pulley_keeps_rope_length() {
+        let (mut world, joint) = pulley_world(real(2.0), real(1.0), real(1.0));
+        // The heavier body pulls the lighter one up
+        for _ in 0..STEPS / 2 {
+            world.step(TIME_STEP);
+            let joint = joint.borrow();
+            let rope = joint.length1() + joint.length2();
+            assert!((rope - real(9.0)).abs() < real(0.01), "{rope}");
+        }
+        let bodies = world.get_bodies();
+        assert!(bodies[0].borrow().position.y < real(-1.0));
+        assert!(bodies[1].borrow().position.y > real(1.0));
+    }
+
+    #[test]
+    fn pulley_ratio_balances_masses() {
+        // The rope pulls body2 with twice the tension, so twice the mass
+        // balances body1
+        let (mut world, joint) = pulley_world(real(1.0), real(2.0), real(2.0));
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+        }
+        let joint = joint.borrow();
+        let rope = joint.length1() + real(2.0) * joint.length2();
+        // Baumgarte leaves a small stretch under a steady load
+        assert!((rope - real(13.5)).abs() < real(0.05), "{rope}");
+        for body in world.get_bodies() {
+            let y = body.borrow().position.y;
+            assert!(y.abs() < real(0.05), "{y}");
+        }
+    }
+
+    #[test]
+    fn motor_joint_reaches_offset() {
+        let soft_step = SolverMode::SoftStep {
+            substeps: 4,
+            contact_hertz: real(30.0),
+            contact_damping_ratio: real(10.0),
+            joint_hertz: real(60.0),
+            joint_damping_ratio: real(2.0),
+        };
+        for solver_mode in [SolverMode::Iterations, soft_step] {
+            let (mut world, body) = single_body_world(real(0.0));
+            world.set_solver_mode(solver_mode);
+            // Relax iterations take back the velocity of each substep, so
+            // the force limit also caps the speed there
+            let joint =
+                world.add_motor_joint(world.ground(), body.clone(), real(1000.0), real(1000.0));
+            joint.borrow_mut().linear_offset = Vec2::new(real(1.0), real(0.5));
+            joint.borrow_mut().angular_offset = real(0.5);
+            for _ in 0..STEPS {
+                world.step(TIME_STEP);
+            }
+            let body = body.borrow();
+            let offset = body.position - Vec2::new(real(1.0), real(0.5));
+            assert!(offset.length() < real(0.01), "{solver_mode:?} {offset:?}");
+            let rotation = body.rotation();
+            assert!(
+                (rotation - real(0.5)).abs() < real(0.01),
+                "{solver_mode:?} {rotation}"
+            );
+            assert!(body.velocity.length() < real(0.01), "{solver_mode:?}");
+        }
+    }
+
+    #[test]
+    fn motor_joint_force_is_limited() {
+        let (mut world, body) = single_body_world(real(0.0));
+        let joint = world.add_motor_joint(world.ground(), body.clone(), real(2.0), real(3.0));
+        joint.borrow_mut().linear_offset = Vec2::new(real(10.0), real(0.0));
+        joint.borrow_mut().angular_offset = real(1.0);
+        world.step(TIME_STEP);
+        // 2 N on 1 kg and 3 N m on the inverse inertia 6
+        let body = body.borrow();
+        assert_close(body.velocity, Vec2::new(real(2.0) * TIME_STEP, real(0.0)));
+        let w = body.angular_velocity;
+        assert!((w - real(18.0) * TIME_STEP).abs() < real(1e-4), "{w}");
+    }
+
+    // Largest sideways offset of a ten box stack settling for four seconds
+    fn stack_drift(block_solver: bool, iterations: i32) -> Real {
+        let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), iterations);
+        world.set_block_solver(block_solver);
+        let floor = Body::new(real(20.0), real(1.0), UNMOVABLE_MASS, real(0.0), real(-0.5));
+        world.add_body(Rc::new(RefCell::new(floor)));
+        for i in 0..10 {
+            let body = Body::new(
+                real(1.0),
+                real(1.0),
+                real(1.0),
+                real(0.0),
+                real(i as f64 + 0.5),
+            );
+            world.add_body(Rc::new(RefCell::new(body)));
+        }
+        for _ in 0..4 * 60 {
+            world.step(TIME_STEP);
+        }
+        world
+            .get_bodies()
+            .iter()
+            .map(|body| body.borrow().position.x.abs())
+            .fold(real(0.0), Real::max)
+    }
+
+    // Solving the two contact points of a box one after the other pushes it
+    // sideways, solving them together keeps the stack straight
+    #[test]
+    fn block_solver_keeps_stack_straight() {
+        for iterations in [4, 10] {
+            let drift = stack_drift(false, iterations);
+            let block_drift = stack_drift(true, iterations);
+            assert!(
+                block_drift < real(0.25) * drift,
+                "{iterations} iterations: {block_drift} {drift}"
+            );
+        }
+    }
+
+    // Speed and height of a unit box starting 0.3 deep in a floor, with no
+    // gravity, after a few steps
+    fn penetration_recovery(position_correction: PositionCorrection) -> (Real, Real) {
+        let mut world = World::new(Vec2::ZERO, 10);
+        world.set_position_correction(position_correction);
+        let floor = Body::new(real(20.0), real(1.0), UNMOVABLE_MASS, real(0.0), real(0.0));
+        world.add_body(Rc::new(RefCell::new(floor)));
+        let body = Rc::new(RefCell::new(Body::new(
+            real(1.0),
+            real(1.0),
+            real(1.0),
+            real(0.0),
+            real(0.7),
+        )));
+        world.add_body(body.clone());
+        for _ in 0..10 {
+            world.step(TIME_STEP);
+        }
+        let body = body.borrow();
+        (body.velocity.length(), body.position.y)
+    }
+
+    #[test]
+    fn penetration_recovery_adds_no_velocity() {
+        // Baumgarte pushes the box out with velocity it keeps afterwards
+        let (speed, height) = penetration_recovery(PositionCorrection::Baumgarte);
+        assert!(speed > real(0.5), "{speed}");
+        assert!(height > real(0.75), "{height}");
+
+        for position_correction in [
+            PositionCorrection::SplitImpulse,
+            PositionCorrection::NonlinearGaussSeidel,
+        ] {
+            let (speed, height) = penetration_recovery(position_correction);
+            assert!(speed < real(1e-3), "{position_correction:?}: {speed}");
+            assert!(height > real(0.75), "{position_correction:?}: {height}");
+        }
+    }
+
+    // Two overlapping unit boxes in zero gravity pinned where they overlap
+    fn overlapping_pair() -> (World, Rc<RefCell<RevoluteJoint>>) {
+        let mut world = World::new(Vec2::ZERO, 10);
+        let [body1, body2] = [0.0, 0.5].map(|x| {
+            let body = Rc::new(RefCell::new(Body::new(
+                real(1.0),
+                real(1.0),
+                real(1.0),
+                real(x),
+                real(0.0),
+            )));
+            world.add_body(body.clone());
+            body
+        });
+        let joint = world.add_revolute_joint(body1, body2, Vec2::new(real(0.25), real(0.0)));
+        (world, joint)
+    }
+
+    #[test]
+    fn jointed_bodies_collide_once_unjointed() {
+        let (mut world, joint) = overlapping_pair();
+        world.step(TIME_STEP);
+        assert!(world.get_collide_points().is_empty());
+
+        joint.borrow_mut().collide_connected = true;
+        world.step(TIME_STEP);
+        assert!(!world.get_collide_points().is_empty());
+
+        let (mut world, joint) = overlapping_pair();
+        world.step(TIME_STEP);
+        world.remove_joint(&joint);
+        world.step(TIME_STEP);
+        assert!(!world.get_collide_points().is_empty());
+
+        // A joint broken during a step stops filtering from the next one
+        let (mut world, joint) = overlapping_pair();
+        joint.borrow_mut().break_force = Some(real(0.1));
+        world.get_bodies()[1].borrow_mut().velocity.y = real(1.0);
+        world.step(TIME_STEP);
+        assert_eq!(world.take_broken_joints().len(), 1);
+        assert!(world.get_collide_points().is_empty());
+        world.step(TIME_STEP);
+        assert!(!world.get_collide_points().is_empty());
+    }
+
+    // Game-side constraint: keeps a body from moving vertically
+    struct Rail {
+        body: Rc<RefCell<Body>>,
+        id: usize,
+    }
+
+    impl Constraint for Rail {
+        fn pre_step(&mut self, step: &mut box2d::StepContext) {
+            self.id = step.bodies.id(&self.body);
+        }
+
+        fn apply_impulse(&mut self, step: &mut box2d::StepContext) {
+            let bodies = &mut *step.bodies;
+            let inv_mass = bodies.linear_inv_mass(self.id).y;
+            if inv_mass > 0.0 {
+                let impulse = -bodies.velocity(self.id).y / inv_mass;
+                bodies.add_velocity(self.id, Vec2::ZERO, Vec2::new(real(0.0), impulse));
+            }
+        }
+
+        fn bodies(&self) -> Vec<Rc<RefCell<Body>>> {
+            vec![self.body.clone()]
+        }
+    }
+
+    #[test]
+    fn custom_constraint_takes_effect() {
+        let mut world = World::new(Vec2::new(real(0.0), real(-10.0)), 10);
+        let body = Rc::new(RefCell::new(Body::new(
+            real(1.0),
+            real(1.0),
+            real(1.0),
+            real(0.0),
+            real(0.0),
+        )));
+        world.add_body(body.clone());
+        body.borrow_mut().velocity.x = real(1.0);
+        let rail = world.add_constraint(Rail {
+            body: body.clone(),
+            id: 0,
+        });
+
+        for _ in 0..STEPS {
+            world.step(TIME_STEP);
+        }
+        assert_eq!(body.borrow().position.y, real(0.0));
+        assert!((body.borrow().position.x - real(2.0)).abs() < real(1e-3));
+
+        world.remove_joint(&rail);
+        world.step(TIME_STEP);
+        assert!(body.borrow().position.y < real(0.0));
+
+        // Boxed trait objects are accepted too
+        let boxed: Box<dyn Constraint> = Box::new(Rail {
+            body: body.clone(),
+            id: 0,
+        });
+        world.add_constraint(boxed);
+        world.step(TIME_STEP);
+        assert_eq!(body.borrow().velocity.y, real(0.0));
+    }
+
+    #[test]
+    fn dragged_body_follows_target() {
+        let mut state = PhysicsState {
+            world: World::new(Vec2::new(real(0.0), GRAVITY), 10),
+            stepper: FixedStepper::new(TIME_STEP, MAX_STEPS),
+            current_scene: 0,
+            mouse_joint: None,
+        };
+        let body = state.add_body(0.2, 0.2, 1.0, 0.0, 0.0);
+        assert!(!state.start_drag(1.0, 1.0));
+        assert!(state.start_drag(0.0, 0.0));
+
+        state.drag(1.0, 0.5);
+        for _ in 0..STEPS {
+            state.step(1.0 / 60.0);
+        }
+        {
+            let body = body.borrow();
+            // The spring holds it against gravity slightly below the target
+            let offset = body.position - to_world(1.0, 0.5);
+            assert!(offset.length() < real(1.0), "{offset:?}");
+            assert!(offset.y < real(0.0), "{offset:?}");
+            assert!(body.velocity.length() < real(0.1), "{:?}", body.velocity);
+        }
+
+        state.end_drag();
+        assert!(state.get_joint_lines().is_empty());
+        for _ in 0..10 {
+            state.step(1.0 / 60.0);
+        }
+        assert!(body.borrow().velocity.y < real(-50.0));
+    }
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < real(1e-4), "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn rot_angle_round_trips() {
+        for angle in [-3.0, -1.0, 0.0, 0.5, 3.0] {
+            let rot = box2d::Rot::from_angle(real(angle));
+            assert!((rot.angle() - real(angle)).abs() < real(1e-4), "{angle}");
+        }
+        let half_turn = box2d::Rot {
+            c: real(-1.0),
+            s: real(0.0),
+        };
+        assert_eq!(half_turn.angle(), box2d::PI);
+    }
+
+    #[test]
+    fn unwind_angle_keeps_pi() {
+        use box2d::{PI, math_utils::unwind_angle};
+        assert_eq!(unwind_angle(PI), PI);
+        assert_eq!(unwind_angle(-PI), PI);
+        assert!((unwind_angle(real(2.0) * PI + real(0.5)) - real(0.5)).abs() < real(1e-4));
+        assert!((unwind_angle(-PI - real(0.5)) - (PI - real(0.5))).abs() < real(1e-4));
+    }
+
+    #[test]
+    fn transform_inverse_and_mul() {
+        let t = Transform::from_angle(Vec2::new(real(1.0), real(2.0)), real(0.7));
+        let u = Transform::from_angle(Vec2::new(real(-0.5), real(0.25)), real(-2.0));
+        let p = Vec2::new(real(-3.0), real(0.5));
+        assert_close(t.inverse() * (t * p), p);
+        assert_close((t * t.inverse()) * p, p);
+        assert_close(t.inv_apply(t.apply(p)), p);
+        assert_close((t * u) * p, t * (u * p));
+    }
+
+    #[test]
+    fn aabb_from_box_and_overlaps() {
+        use box2d::Aabb;
+        let aabb = Aabb::from_box(
+            Transform::from_angle(Vec2::new(real(1.0), real(0.0)), real(0.0)),
+            Vec2::new(real(2.0), real(1.0)),
+        );
+        assert_close(aabb.lower, Vec2::new(real(-1.0), real(-1.0)));
+        assert_close(aabb.upper, Vec2::new(real(3.0), real(1.0)));
+
+        // A unit square turned by 45 degrees reaches out to sqrt(2)
+        let turned = Aabb::from_box(
+            Transform::from_angle(Vec2::ZERO, real(0.25) * box2d::PI),
+            Vec2::new(real(1.0), real(1.0)),
+        );
+        let reach = box2d::sqrt(real(2.0));
+        assert_close(turned.upper, Vec2::new(reach, reach));
+
+        let touching = Aabb::new(
+            Vec2::new(real(3.0), real(0.0)),
+            Vec2::new(real(4.0), real(1.0)),
+        );
+        let apart = Aabb::new(
+            Vec2::new(real(3.5), real(0.0)),
+            Vec2::new(real(4.0), real(1.0)),
+        );
+        assert!(aabb.overlaps(touching) && touching.overlaps(aabb));
+        assert!(!aabb.overlaps(apart) && !apart.overlaps(aabb));
+    }
+
+    #[test]
+    #[cfg(feature = "fixed-point")]
+    fn fixed_atan2_and_sqrt() {
+        use box2d::Fixed;
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+        let fixed = Fixed::from_f64;
+        let close = |a: Fixed, b: f64| (a.to_f64() - b).abs() < 1e-7;
+
+        assert_eq!(fixed(4.0).sqrt(), fixed(2.0));
+        assert!(close(fixed(2.0).sqrt(), std::f64::consts::SQRT_2));
+        assert_eq!(fixed(-1.0).sqrt(), Fixed::ZERO);
+
+        assert!(close(fixed(1.0).atan2(fixed(1.0)), FRAC_PI_4));
+        assert!(close(fixed(1.0).atan2(fixed(-1.0)), 3.0 * FRAC_PI_4));
+        assert!(close(fixed(-1.0).atan2(fixed(-1.0)), -3.0 * FRAC_PI_4));
+        assert!(close(fixed(-2.0).atan2(fixed(1.0)), (-2.0f64).atan2(1.0)));
+        assert!(close(fixed(1.0).atan2(Fixed::ZERO), FRAC_PI_2));
+        assert_eq!(fixed(0.0).atan2(fixed(-1.0)), Fixed::PI);
+        assert_eq!(Fixed::ZERO.atan2(Fixed::ZERO), Fixed::ZERO);
+    }
+}